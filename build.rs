@@ -1,8 +1,39 @@
-use git2::Repository;
+//! Builds `token_map.json` from the Secret tokens in Keplr's contract registry.
+//!
+//! The registry is read from the first of these that works:
+//!
+//! 1. a local checkout at `KEPLR_REGISTRY_DIR`,
+//! 2. a clone in `OUT_DIR`, checked out at `KEPLR_REGISTRY_REV`, or else at the revision the
+//!    snapshot was made from (skipped when `KEPLR_REGISTRY_OFFLINE` is set, or when neither
+//!    revision is known),
+//! 3. the snapshot vendored at `registry/secret-tokens.json`.
+//!
+//! Set `KEPLR_REGISTRY_UPDATE_SNAPSHOT` to overwrite the snapshot with whatever was read from
+//! the registry. The commit it was read at goes to `registry/secret-tokens.rev`, so clones and
+//! offline builds agree on the tokens. The registry's moving `HEAD` is never built from. To move
+//! to a newer registry:
+//!
+//! ```sh
+//! KEPLR_REGISTRY_REV=<commit> KEPLR_REGISTRY_UPDATE_SNAPSHOT=1 cargo build
+//! ```
+
+use git2::{build::CheckoutBuilder, Oid, Repository};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+const REPO_URL: &str = "https://github.com/chainapsis/keplr-contract-registry.git";
+const REPO_DIR: &str = "keplr-contract-registry";
+const SECRET_DIR: &str = "cosmos/secret/tokens";
+const SNAPSHOT: &str = "registry/secret-tokens.json";
+const SNAPSHOT_REV: &str = "registry/secret-tokens.rev";
+
+const REGISTRY_DIR_VAR: &str = "KEPLR_REGISTRY_DIR";
+const REGISTRY_REV_VAR: &str = "KEPLR_REGISTRY_REV";
+const OFFLINE_VAR: &str = "KEPLR_REGISTRY_OFFLINE";
+const UPDATE_SNAPSHOT_VAR: &str = "KEPLR_REGISTRY_UPDATE_SNAPSHOT";
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -20,53 +51,180 @@ struct ContractInfo {
     metadata: Metadata,
 }
 
+// sorted, so the snapshot diffs cleanly when it's updated
+type TokenMap = BTreeMap<String, ContractInfo>;
+
 fn main() {
-    // 1. Define the repository and directory to clone
-    let repo_url = "https://github.com/chainapsis/keplr-contract-registry.git";
-    let repo_dir = "keplr-contract-registry";
-    let secret_dir = "cosmos/secret/tokens";
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={SNAPSHOT}");
+    println!("cargo:rerun-if-changed={SNAPSHOT_REV}");
+    for var in [REGISTRY_DIR_VAR, REGISTRY_REV_VAR, OFFLINE_VAR, UPDATE_SNAPSHOT_VAR] {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
 
-    // 2. Set the output directory
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
-    let clone_dir = out_dir.join(repo_dir);
 
-    // 3. Clone the repository (shallow clone)
-    if !clone_dir.exists() {
-        println!("Cloning repository...");
-        Repository::clone(repo_url, &clone_dir).expect("Failed to clone repository");
+    let registry = registry_checkout(&out_dir)
+        .and_then(|(dir, rev)| read_registry(&dir).map(|token_map| (token_map, rev)));
+    let token_map = match registry {
+        Ok((token_map, rev)) => {
+            if env::var_os(UPDATE_SNAPSHOT_VAR).is_some() {
+                update_snapshot(&token_map, rev);
+            }
+            token_map
+        }
+        Err(error) => {
+            let rev = snapshot_rev().unwrap_or_else(|| "an unrecorded revision".to_string());
+            warning(&format!(
+                "{error}; using the vendored snapshot at {SNAPSHOT}, made from {rev}"
+            ));
+            read_snapshot()
+        }
+    };
+
+    write_json(&out_dir.join("token_map.json"), &token_map);
+}
+
+/// Finds or clones the registry, returning the directory it's in and the commit checked out
+/// there, if it's a git checkout.
+fn registry_checkout(out_dir: &Path) -> Result<(PathBuf, Option<Oid>), String> {
+    if let Some(dir) = env::var_os(REGISTRY_DIR_VAR) {
+        let dir = PathBuf::from(dir);
+        println!("cargo:rerun-if-changed={}", dir.join(SECRET_DIR).display());
+        if !dir.join(SECRET_DIR).is_dir() {
+            return Err(format!(
+                "{REGISTRY_DIR_VAR}={} has no {SECRET_DIR} directory",
+                dir.display()
+            ));
+        }
+        let rev = Repository::open(&dir)
+            .and_then(|repo| repo.head()?.peel_to_commit())
+            .map(|commit| commit.id())
+            .ok();
+        return Ok((dir, rev));
+    }
+
+    if env::var_os(OFFLINE_VAR).is_some() {
+        return Err(format!("{OFFLINE_VAR} is set"));
     }
 
-    // 4. Define the directory to work with
-    let tokens_dir = clone_dir.join(secret_dir);
+    // an unpinned clone could disagree with the snapshot that offline builds use
+    let Some(rev) = env::var(REGISTRY_REV_VAR).ok().or_else(snapshot_rev) else {
+        return Err(format!(
+            "no registry revision is pinned in {REGISTRY_REV_VAR} or {SNAPSHOT_REV}"
+        ));
+    };
+
+    let clone_dir = out_dir.join(REPO_DIR);
+    let repo = match Repository::open(&clone_dir) {
+        Ok(repo) => repo,
+        Err(_) => {
+            // a previous clone may have been interrupted
+            let _ = fs::remove_dir_all(&clone_dir);
+            Repository::clone(REPO_URL, &clone_dir)
+                .map_err(|e| format!("failed to clone {REPO_URL}: {}", e.message()))?
+        }
+    };
+
+    let rev = checkout(&repo, &rev)?;
+    Ok((clone_dir, Some(rev)))
+}
 
-    // 5. Iterate over all JSON files in the `tokens` directory
-    let mut token_map = std::collections::HashMap::new();
+/// Checks out `rev`, fetching first if the clone doesn't have it yet.
+fn checkout(repo: &Repository, rev: &str) -> Result<Oid, String> {
+    let oid = match Oid::from_str(rev).and_then(|oid| repo.find_commit(oid)) {
+        Ok(commit) => commit.id(),
+        Err(_) => {
+            repo.find_remote("origin")
+                .and_then(|mut remote| remote.fetch(&[rev], None, None))
+                .map_err(|e| format!("failed to fetch {rev}: {}", e.message()))?;
+            repo.revparse_single(rev)
+                .or_else(|_| repo.revparse_single("FETCH_HEAD"))
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|e| format!("unknown registry revision {rev}: {}", e.message()))?
+                .id()
+        }
+    };
 
-    for entry in fs::read_dir(&tokens_dir).expect("Failed to read tokens directory") {
-        let entry = entry.expect("Failed to read entry");
-        let path = entry.path();
+    let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))
+        .and_then(|_| repo.set_head_detached(oid))
+        .map_err(|e| format!("failed to check out {rev}: {}", e.message()))?;
+    Ok(oid)
+}
 
-        println!("{:?}", entry);
+fn read_registry(dir: &Path) -> Result<TokenMap, String> {
+    let tokens_dir = dir.join(SECRET_DIR);
+    let entries = fs::read_dir(&tokens_dir)
+        .map_err(|e| format!("failed to read {}: {e}", tokens_dir.display()))?;
 
-        if path.extension() == Some(std::ffi::OsStr::new("json")) {
-            let file_name = path.file_stem().unwrap().to_str().unwrap().to_string();
-            let file_content = fs::read_to_string(&path).expect("Failed to read file");
+    let mut token_map = TokenMap::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension() != Some(std::ffi::OsStr::new("json")) {
+            continue;
+        }
 
-            // Deserialize the file content into a ContractInfo struct
-            let contract_info: ContractInfo = serde_json::from_str(&file_content)
-                .expect("Failed to parse JSON into ContractInfo");
+        let Some(file_name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let contract_info = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()));
 
-            // Insert the ContractInfo struct into the HashMap
-            token_map.insert(file_name, contract_info);
+        // one malformed entry shouldn't cost us the whole registry
+        match contract_info {
+            Ok(contract_info) => {
+                token_map.insert(file_name.to_string(), contract_info);
+            }
+            Err(error) => warning(&format!("skipping {}: {error}", path.display())),
         }
     }
 
-    // 6. Serialize the HashMap and write it to the build directory
-    let serialized = serde_json::to_string(&token_map).expect("Failed to serialize HashMap");
-    let map_file_path = out_dir.join("token_map.json");
-    fs::write(&map_file_path, serialized).expect("Failed to write token_map.json");
+    match token_map.is_empty() {
+        true => Err(format!("no tokens found in {}", tokens_dir.display())),
+        false => Ok(token_map),
+    }
+}
 
-    // 7. Instruct Cargo to re-run the build script if any files in the tokens directory change
-    println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed={}", tokens_dir.to_str().unwrap());
+/// The registry commit the snapshot was made from.
+fn snapshot_rev() -> Option<String> {
+    fs::read_to_string(SNAPSHOT_REV)
+        .ok()
+        .map(|rev| rev.trim().to_string())
+        .filter(|rev| !rev.is_empty())
+}
+
+fn update_snapshot(token_map: &TokenMap, rev: Option<Oid>) {
+    // a snapshot nobody can reproduce is worse than an old one
+    let Some(rev) = rev else {
+        panic!("{UPDATE_SNAPSHOT_VAR} needs the registry in a git checkout, to record its commit");
+    };
+    write_json(Path::new(SNAPSHOT), token_map);
+    if let Err(error) = fs::write(SNAPSHOT_REV, format!("{rev}\n")) {
+        panic!("failed to write {SNAPSHOT_REV}: {error}");
+    }
+}
+
+fn read_snapshot() -> TokenMap {
+    fs::read_to_string(SNAPSHOT)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|error| {
+            // the app still works without tokens, it just can't show any
+            warning(&format!("failed to read {SNAPSHOT}: {error}; building without tokens"));
+            TokenMap::new()
+        })
+}
+
+fn write_json(path: &Path, token_map: &TokenMap) {
+    let serialized =
+        serde_json::to_string_pretty(token_map).expect("token map is always serializable");
+    if let Err(error) = fs::write(path, serialized + "\n") {
+        panic!("failed to write {}: {error}", path.display());
+    }
+}
+
+fn warning(message: &str) {
+    println!("cargo:warning={message}");
 }
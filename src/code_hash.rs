@@ -0,0 +1,109 @@
+//! Resolving contract code hashes.
+//!
+//! Every encrypted query needs the code hash of the contract it's sent to. A contract's code hash
+//! only changes when it's migrated, which registry tokens effectively never are, so hashes are
+//! cached in memory and in localStorage, per chain.
+
+use crate::{config::ChainConfig, error::Error, state::WasmClient, storage};
+use leptos::prelude::*;
+use rsecret::query::compute::ComputeQuerier;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// contract address -> code hash
+type CodeHashMap = HashMap<String, String>;
+
+/// Provided as context, next to [`WasmClient`].
+#[derive(Clone, Copy)]
+pub struct CodeHashes {
+    chain: RwSignal<ChainConfig>,
+    wasm_client: WasmClient,
+    /// chain id -> that chain's hashes
+    cache: StoredValue<HashMap<String, CodeHashMap>>,
+}
+
+impl CodeHashes {
+    pub fn new(chain: RwSignal<ChainConfig>, wasm_client: WasmClient) -> Self {
+        Self {
+            chain,
+            wasm_client,
+            cache: StoredValue::new(HashMap::new()),
+        }
+    }
+
+    fn storage_key(chain_id: &str) -> String {
+        format!("secret-leptos:code-hashes:{chain_id}")
+    }
+
+    /// The cached code hash of `contract_address` on the current chain, if there is one.
+    pub fn cached(&self, contract_address: &str) -> Option<String> {
+        let chain_id = self.chain.get_untracked().chain_id;
+
+        if !self.cache.with_value(|cache| cache.contains_key(chain_id)) {
+            let stored: CodeHashMap =
+                storage::load(&Self::storage_key(chain_id)).unwrap_or_default();
+            self.cache
+                .update_value(|cache| _ = cache.insert(chain_id.to_string(), stored));
+        }
+
+        self.cache.with_value(|cache| {
+            cache
+                .get(chain_id)
+                .and_then(|hashes| hashes.get(contract_address))
+                .cloned()
+        })
+    }
+
+    /// Caches the code hash of `contract_address` on `chain_id` and returns it normalized.
+    pub fn insert(&self, chain_id: &str, contract_address: &str, code_hash: &str) -> String {
+        // queriers may return the hash in upper case or with a `0x` prefix
+        let code_hash = code_hash.trim_start_matches("0x").to_lowercase();
+
+        let hashes = self.cache.try_update_value(|cache| {
+            let hashes = cache.entry(chain_id.to_string()).or_default();
+            hashes.insert(contract_address.to_string(), code_hash.clone());
+            hashes.clone()
+        });
+        if let Some(hashes) = hashes {
+            storage::save(&Self::storage_key(chain_id), &hashes);
+        }
+        code_hash
+    }
+
+    /// The code hash of `contract_address` on the current chain, asking the compute module when
+    /// it isn't cached yet.
+    pub async fn resolve(&self, contract_address: &str) -> Result<String, Error> {
+        if let Some(code_hash) = self.cached(contract_address) {
+            return Ok(code_hash);
+        }
+
+        debug!("resolving code hash of {contract_address}");
+        // the user may switch networks while the query is in flight
+        let chain = self.chain.get_untracked();
+        let options = chain.querier_options()?;
+        let code_hash = self
+            .wasm_client
+            .query(|client| {
+                let compute = ComputeQuerier::new(client, options.clone());
+                let contract_address = contract_address.to_string();
+                async move {
+                    compute
+                        .code_hash_by_contract_address(&contract_address)
+                        .await
+                }
+            })
+            .await?;
+
+        Ok(self.insert(chain.chain_id, contract_address, &code_hash))
+    }
+
+    /// Resolves the code hashes of `contract_addresses` concurrently, in the same order.
+    pub async fn resolve_all(&self, contract_addresses: &[String]) -> Result<Vec<String>, Error> {
+        futures::future::try_join_all(
+            contract_addresses
+                .iter()
+                .map(|contract_address| self.resolve(contract_address)),
+        )
+        .await
+    }
+}
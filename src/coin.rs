@@ -0,0 +1,379 @@
+//! Token amounts with their decimals.
+
+use crate::{config::ChainConfig, keplr::tokens::ContractInfo};
+use secretrs::proto::cosmos::{bank::v1beta1::Metadata, base::v1beta1::Coin as ProtoCoin};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt};
+
+#[derive(thiserror::Error, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AmountError {
+    #[error("Amount is empty")]
+    Empty,
+
+    #[error("{0:?} is not a valid amount")]
+    Invalid(String),
+
+    #[error("At most {0} decimal places are allowed")]
+    TooPrecise(u8),
+
+    #[error("Amount is too large")]
+    Overflow,
+
+    #[error("Amounts with {0} and {1} decimals can't be combined")]
+    DecimalsMismatch(u8, u8),
+
+    #[error("Use a dot for decimals and no thousands separators")]
+    Comma,
+}
+
+/// An exact token amount: `raw` base units, with `decimals` digits after the decimal point.
+///
+/// `Amount::new(1_234_567, 6)` is 1.234567.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Amount {
+    raw: u128,
+    decimals: u8,
+}
+
+impl Amount {
+    pub const fn new(raw: u128, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub const fn zero(decimals: u8) -> Self {
+        Self::new(0, decimals)
+    }
+
+    /// Amount in base units, e.g. uscrt.
+    pub const fn raw(&self) -> u128 {
+        self.raw
+    }
+
+    pub const fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+
+    /// Parses an amount in base units, as found in chain responses (`"1234567"`).
+    pub fn from_raw_str(raw: &str, decimals: u8) -> Result<Self, AmountError> {
+        if raw.is_empty() {
+            return Err(AmountError::Empty);
+        }
+        raw.parse()
+            .map(|raw| Self::new(raw, decimals))
+            .map_err(|_| AmountError::Invalid(raw.to_string()))
+    }
+
+    /// Parses an amount as a user would type it (`"1.5"`), rejecting more digits than `decimals`.
+    ///
+    /// Commas are rejected rather than guessed at: `"1,5"` is 1.5 in some locales and 15 in
+    /// others.
+    pub fn parse(input: &str, decimals: u8) -> Result<Self, AmountError> {
+        if input.contains(',') {
+            return Err(AmountError::Comma);
+        }
+        let input = input.trim().replace('_', "");
+        if input.is_empty() {
+            return Err(AmountError::Empty);
+        }
+        let invalid = || AmountError::Invalid(input.clone());
+
+        let (whole, fraction) = match input.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (input.as_str(), ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        // trailing zeros don't add precision
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(AmountError::TooPrecise(decimals));
+        }
+        let unit = Self::unit(decimals).ok_or(AmountError::Overflow)?;
+
+        let whole: u128 = match whole {
+            "" => 0,
+            whole => whole.parse().map_err(|_| AmountError::Overflow)?,
+        };
+        let fraction: u128 = match fraction {
+            "" => 0,
+            fraction => {
+                fraction.parse::<u128>().map_err(|_| invalid())?
+                    * 10u128.pow((decimals as usize - fraction.len()) as u32)
+            }
+        };
+
+        whole
+            .checked_mul(unit)
+            .and_then(|whole| whole.checked_add(fraction))
+            .map(|raw| Self::new(raw, decimals))
+            .ok_or(AmountError::Overflow)
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        self.raw
+            .checked_add(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+            .ok_or(AmountError::Overflow)
+    }
+
+    /// Fails with [`AmountError::Overflow`] if `other` is larger, since amounts can't be negative.
+    pub fn checked_sub(self, other: Self) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        self.raw
+            .checked_sub(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+            .ok_or(AmountError::Overflow)
+    }
+
+    pub fn checked_mul(self, factor: u128) -> Result<Self, AmountError> {
+        self.raw
+            .checked_mul(factor)
+            .map(|raw| Self::new(raw, self.decimals))
+            .ok_or(AmountError::Overflow)
+    }
+
+    fn same_decimals(&self, other: &Self) -> Result<(), AmountError> {
+        match self.decimals == other.decimals {
+            true => Ok(()),
+            false => Err(AmountError::DecimalsMismatch(self.decimals, other.decimals)),
+        }
+    }
+
+    fn unit(decimals: u8) -> Option<u128> {
+        10u128.checked_pow(decimals as u32)
+    }
+}
+
+impl PartialOrd for Amount {
+    /// Only amounts with the same decimals can be compared.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.same_decimals(other).ok()?;
+        Some(self.raw.cmp(&other.raw))
+    }
+}
+
+impl fmt::Display for Amount {
+    /// Exact, without trailing zeros: `1.2345`, `1`, `0.000001`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(unit) = Self::unit(self.decimals) else {
+            // more decimals than fit in a u128, so the whole part is always 0
+            let digits = format!("{:0>width$}", self.raw, width = self.decimals as usize);
+            return write!(f, "0.{}", digits.trim_end_matches('0'));
+        };
+
+        let whole = self.raw / unit;
+        let fraction = self.raw % unit;
+        if fraction == 0 {
+            return write!(f, "{whole}");
+        }
+
+        let fraction = format!("{fraction:0>width$}", width = self.decimals as usize);
+        write!(f, "{whole}.{}", fraction.trim_end_matches('0'))
+    }
+}
+
+/// How to display a denom: its symbol and the number of decimals of the display unit.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DenomInfo {
+    /// Base denom (`uscrt`) or contract address.
+    pub denom: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl From<&ChainConfig> for DenomInfo {
+    fn from(chain: &ChainConfig) -> Self {
+        Self {
+            denom: chain.denom.to_string(),
+            symbol: chain.display_denom.to_string(),
+            decimals: chain.decimals,
+        }
+    }
+}
+
+impl From<&ContractInfo> for DenomInfo {
+    fn from(token: &ContractInfo) -> Self {
+        Self {
+            denom: token.contract_address.clone(),
+            symbol: token.metadata.symbol.clone(),
+            decimals: token.metadata.decimals,
+        }
+    }
+}
+
+impl TryFrom<&Metadata> for DenomInfo {
+    type Error = AmountError;
+
+    /// Uses the exponent of the `display` unit from the bank module's denom metadata.
+    fn try_from(metadata: &Metadata) -> Result<Self, Self::Error> {
+        let exponent = metadata
+            .denom_units
+            .iter()
+            .find(|unit| unit.denom == metadata.display)
+            .map(|unit| unit.exponent)
+            .ok_or_else(|| AmountError::Invalid(metadata.display.clone()))?;
+
+        Ok(Self {
+            denom: metadata.base.clone(),
+            symbol: match metadata.symbol.is_empty() {
+                true => metadata.display.to_uppercase(),
+                false => metadata.symbol.clone(),
+            },
+            decimals: u8::try_from(exponent).map_err(|_| AmountError::Overflow)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Coin {
+    /// Base denom (`uscrt`) or contract address.
+    pub denom: String,
+    pub symbol: String,
+    pub amount: Amount,
+}
+
+impl Coin {
+    pub fn new(info: &DenomInfo, amount: Amount) -> Self {
+        Self {
+            denom: info.denom.clone(),
+            symbol: info.symbol.clone(),
+            amount,
+        }
+    }
+
+    /// Parses user input for the given denom.
+    pub fn parse(input: &str, info: &DenomInfo) -> Result<Self, AmountError> {
+        Amount::parse(input, info.decimals).map(|amount| Self::new(info, amount))
+    }
+
+    pub fn from_proto(coin: ProtoCoin, info: &DenomInfo) -> Result<Self, AmountError> {
+        let amount = Amount::from_raw_str(&coin.amount, info.decimals)?;
+        Ok(Self {
+            denom: coin.denom,
+            symbol: info.symbol.clone(),
+            amount,
+        })
+    }
+
+    /// `amount` is the raw `Uint128` from a SNIP-20 `balance` (or similar) response.
+    pub fn from_snip20(amount: u128, token: &ContractInfo) -> Self {
+        Self::new(
+            &DenomInfo::from(token),
+            Amount::new(amount, token.metadata.decimals),
+        )
+    }
+
+    /// The amount in base units, as the chain expects it.
+    pub fn to_proto(&self) -> ProtoCoin {
+        ProtoCoin {
+            denom: self.denom.clone(),
+            amount: self.amount.raw().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Coin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn display() {
+        assert_eq!(Amount::new(1_234_567, 6).to_string(), "1.234567");
+        assert_eq!(Amount::new(1_500_000, 6).to_string(), "1.5");
+        assert_eq!(Amount::new(2_000_000, 6).to_string(), "2");
+        assert_eq!(Amount::new(1, 6).to_string(), "0.000001");
+        assert_eq!(Amount::new(42, 0).to_string(), "42");
+        assert_eq!(
+            Amount::new(u128::MAX, 18).to_string(),
+            "340282366920938463463.374607431768211455"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn parse() {
+        assert_eq!(Amount::parse("1.234567", 6), Ok(Amount::new(1_234_567, 6)));
+        assert_eq!(
+            Amount::parse(" 1_000.5 ", 6),
+            Ok(Amount::new(1_000_500_000, 6))
+        );
+        assert_eq!(Amount::parse(" 1,000.5 ", 6), Err(AmountError::Comma));
+        assert_eq!(Amount::parse("1,5", 6), Err(AmountError::Comma));
+        assert_eq!(Amount::parse(".5", 6), Ok(Amount::new(500_000, 6)));
+        assert_eq!(Amount::parse("5.", 6), Ok(Amount::new(5_000_000, 6)));
+        assert_eq!(Amount::parse("1.5000000", 6), Ok(Amount::new(1_500_000, 6)));
+        assert_eq!(
+            Amount::parse("1.2345678", 6),
+            Err(AmountError::TooPrecise(6))
+        );
+        assert_eq!(Amount::parse("", 6), Err(AmountError::Empty));
+        assert_eq!(
+            Amount::parse("-1", 6),
+            Err(AmountError::Invalid("-1".into()))
+        );
+        assert_eq!(
+            Amount::parse("1e6", 6),
+            Err(AmountError::Invalid("1e6".into()))
+        );
+        assert_eq!(Amount::parse(".", 6), Err(AmountError::Invalid(".".into())));
+        assert_eq!(
+            Amount::parse("340282366920938463463374607431768211455", 6),
+            Err(AmountError::Overflow)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn arithmetic() {
+        let a = Amount::new(1_500_000, 6);
+        let b = Amount::new(500_000, 6);
+
+        assert_eq!(a.checked_add(b), Ok(Amount::new(2_000_000, 6)));
+        assert_eq!(a.checked_sub(b), Ok(Amount::new(1_000_000, 6)));
+        assert_eq!(b.checked_sub(a), Err(AmountError::Overflow));
+        assert_eq!(a.checked_mul(3), Ok(Amount::new(4_500_000, 6)));
+        assert_eq!(
+            a.checked_add(Amount::new(1, 18)),
+            Err(AmountError::DecimalsMismatch(6, 18))
+        );
+        assert!(a > b);
+        assert_eq!(a.partial_cmp(&Amount::new(1, 18)), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn coin() {
+        let scrt = DenomInfo {
+            denom: "uscrt".to_string(),
+            symbol: "SCRT".to_string(),
+            decimals: 6,
+        };
+        let proto = ProtoCoin {
+            denom: "uscrt".to_string(),
+            amount: "1234567".to_string(),
+        };
+
+        let coin = Coin::from_proto(proto.clone(), &scrt).unwrap();
+        assert_eq!(coin.to_string(), "1.234567 SCRT");
+        assert_eq!(coin.to_proto(), proto);
+        assert_eq!(Coin::parse("1.234567", &scrt), Ok(coin));
+    }
+}
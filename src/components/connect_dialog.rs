@@ -0,0 +1,51 @@
+use crate::keplr::Wallet;
+use leptos::{html::Dialog, prelude::*};
+use tracing::info;
+
+#[component]
+pub fn ConnectDialog(
+    dialog_ref: NodeRef<Dialog>,
+    on_select: impl Fn(Wallet) + Copy + 'static,
+) -> impl IntoView {
+    info!("rendering <ConnectDialog/>");
+
+    let close = move || {
+        if let Some(dialog) = dialog_ref.get() {
+            dialog.close()
+        }
+    };
+
+    // extensions inject themselves before the page is interactive, so checking once is enough
+    let wallets = Wallet::detected();
+    let no_wallets = wallets.is_empty();
+
+    let wallet_buttons = wallets
+        .into_iter()
+        .map(|wallet| {
+            view! {
+                <button
+                    on:click=move |_| {
+                        close();
+                        on_select(wallet);
+                    }
+                    class="self-stretch"
+                >
+                    {wallet.to_string()}
+                </button>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <dialog node_ref=dialog_ref class="absolute inset-0 flex flex-col gap-4 items-center">
+            <h2>"Connect Wallet"</h2>
+            <Show when=move || no_wallets>
+                <p>"No wallet extension found. Install Keplr or Leap and reload the page."</p>
+            </Show>
+            {wallet_buttons}
+            <button on:click=move |_| close() class="self-stretch">
+                "Cancel"
+            </button>
+        </dialog>
+    }
+}
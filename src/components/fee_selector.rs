@@ -0,0 +1,51 @@
+use crate::{
+    config::ChainConfig,
+    fee::{fee_options, FeeChoice},
+};
+use leptos::prelude::*;
+
+/// Radio buttons for every fee tier in every fee currency of the current chain, with the fee
+/// each would cost at `gas_limit`.
+#[component]
+pub fn FeeSelector(
+    #[prop(into)] gas_limit: Signal<u64>,
+    selected: RwSignal<FeeChoice>,
+) -> impl IntoView {
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+
+    let options = move || {
+        let chain = chain.get();
+        let gas_limit = gas_limit.get();
+        let first_denom = chain.fee_denoms.first().map(|fee_denom| fee_denom.denom);
+
+        fee_options(&chain, gas_limit)
+            .into_iter()
+            .map(|(choice, fee)| {
+                // no explicit denom means the first one
+                let checked = move || {
+                    let selected = selected.get();
+                    selected.tier == choice.tier && selected.denom.or(first_denom) == choice.denom
+                };
+                view! {
+                    <label class="flex gap-2 items-center">
+                        <input
+                            type="radio"
+                            name="fee"
+                            prop:checked=checked
+                            on:change=move |_| selected.set(choice)
+                        />
+                        <span>{fee.tier.to_string()}</span>
+                        <span class="ml-auto font-mono">{fee.amount.to_string()}</span>
+                    </label>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <fieldset class="flex flex-col gap-1">
+            <legend class="text-sm">"Fee (gas limit " {move || gas_limit.get()} ")"</legend>
+            {options}
+        </fieldset>
+    }
+}
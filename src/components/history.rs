@@ -0,0 +1,144 @@
+use crate::{
+    config::ChainConfig,
+    history::{history_page, HistoryCursor, HistoryPage, HistoryRow},
+    state::{KeplrSignals, TokenMap, WasmClient},
+};
+use leptos::prelude::*;
+use send_wrapper::SendWrapper;
+use tracing::{error, info};
+
+/// The connected account's transactions, newest first, a page at a time.
+#[component]
+pub fn History() -> impl IntoView {
+    info!("rendering <History/>");
+
+    let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+    let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
+    let token_map = use_context::<Memo<TokenMap>>().expect("tokens context missing!");
+
+    // where each page shown so far starts, the current one last
+    let cursors = RwSignal::new(vec![HistoryCursor::default()]);
+    let revalidated = Trigger::new();
+
+    let address = Memo::new(move |_| {
+        keplr
+            .key
+            .get()
+            .and_then(Result::ok)
+            .map(|key| key.bech32_address)
+    });
+    // another account has its own pages
+    Effect::new(move |_| {
+        address.track();
+        cursors.set(vec![HistoryCursor::default()]);
+    });
+
+    let history = LocalResource::new(move || {
+        let address = address.get();
+        let chain = chain.get();
+        let cursor = cursors.with(|cursors| cursors.last().copied().unwrap_or_default());
+        wasm_client.refresh.track();
+        revalidated.track();
+
+        SendWrapper::new(async move {
+            let Some(address) = address else {
+                return Ok(HistoryPage::default());
+            };
+            history_page(wasm_client, chain, address, cursor, revalidated)
+                .await
+                .inspect_err(|error| error!("{error}"))
+        })
+    });
+
+    let row_view = move |row: HistoryRow| {
+        let address = address.get_untracked().unwrap_or_default();
+        let tokens = token_map.get_untracked();
+        let explorer = chain.get_untracked().tx_url(&row.tx_hash);
+        let descriptions = row
+            .messages
+            .iter()
+            .map(|msg| view! { <li>{msg.describe(&address, &tokens)}</li> })
+            .collect_view();
+        let status = match row.code {
+            0 => String::new(),
+            code => format!("Failed (code {code})"),
+        };
+        view! {
+            <li class="flex flex-col gap-1 text-sm">
+                <div class="flex gap-4 text-neutral-500">
+                    <span>"Block " {row.height}</span>
+                    <span>{row.timestamp}</span>
+                    <span class="text-red-500">{status}</span>
+                    {explorer
+                        .map(|url| {
+                            view! {
+                                <a href=url target="_blank" class="ml-auto">
+                                    "Details"
+                                </a>
+                            }
+                        })}
+                </div>
+                <ul>{descriptions}</ul>
+            </li>
+        }
+    };
+
+    let rows = move || {
+        Suspend::new(async move {
+            match history.await {
+                Ok(HistoryPage { rows, .. }) if rows.is_empty() => {
+                    view! { <p>"No transactions."</p> }.into_any()
+                }
+                Ok(HistoryPage { rows, .. }) => view! {
+                    <ul class="flex flex-col gap-4">
+                        {rows.into_iter().map(row_view).collect_view()}
+                    </ul>
+                }
+                .into_any(),
+                Err(error) => {
+                    view! { <p class="text-sm text-red-500">{error.to_string()}</p> }.into_any()
+                }
+            }
+        })
+    };
+
+    let next = move || {
+        history
+            .get()
+            .and_then(Result::ok)
+            .and_then(|page| page.next)
+    };
+    let page = move || cursors.with(Vec::len);
+    let newer = move |_| {
+        cursors.update(|cursors| {
+            if cursors.len() > 1 {
+                cursors.pop();
+            }
+        })
+    };
+    let older = move |_| {
+        if let Some(next) = next() {
+            cursors.update(|cursors| cursors.push(next));
+        }
+    };
+
+    view! {
+        <Show
+            when=move || keplr.enabled.get()
+            fallback=|| view! { <p>"Connect a wallet to see your transactions."</p> }
+        >
+            <h2>"History"</h2>
+            <Suspense fallback=move || view! { <p>"Loading transactions..."</p> }>{rows}</Suspense>
+            <div class="flex gap-4 items-center">
+                <button on:click=newer disabled=move || page() == 1>
+                    "Newer"
+                </button>
+                <span class="text-sm">"Page " {page}</span>
+                <button on:click=older disabled=move || next().is_none()>
+                    "Older"
+                </button>
+            </div>
+        </Show>
+    }
+}
@@ -1,5 +1,19 @@
+mod connect_dialog;
+mod fee_selector;
+mod history;
+mod portfolio;
+mod send;
 mod spinner;
 mod spinner2;
+mod token_transfer;
+mod tx_toasts;
 
+pub use connect_dialog::ConnectDialog;
+pub use fee_selector::FeeSelector;
+pub use history::History;
+pub use portfolio::{Portfolio, TokenBalance};
+pub use send::{validate_address, SendTokens};
 pub use spinner::Spinner;
 pub use spinner2::Spinner2;
+pub use token_transfer::TokenTransfer;
+pub use tx_toasts::TxToasts;
@@ -0,0 +1,375 @@
+use crate::{
+    code_hash::CodeHashes,
+    coin::Coin,
+    components::{FeeSelector, TokenTransfer},
+    config::ChainConfig,
+    contract::query_contract,
+    enigma::Enigma,
+    error::Error,
+    fee::FeeChoice,
+    keplr::{tokens::ContractInfo, KeplrEnigmaUtils, WalletProvider},
+    permit::{query_with_permit, Snip20QueryWithPermit},
+    state::{KeplrSignals, TokenMap, WasmClient},
+    tx::SigningClient,
+    tx_tracker::TxTracker,
+    viewing_keys::{self, setup_viewing_keys, ViewingKeyMethod},
+};
+use leptos::prelude::*;
+use secret_toolkit_snip20::{BalanceResponse, QueryMsg};
+use send_wrapper::SendWrapper;
+use std::collections::HashSet;
+use tracing::{debug, error, info};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenBalance {
+    Balance(Coin),
+    /// There's no viewing key or permit for this token.
+    Locked,
+    /// The query failed, for example because the viewing key is wrong.
+    Unknown(String),
+}
+
+async fn token_balance(
+    keplr: KeplrSignals,
+    wasm_client: WasmClient,
+    code_hashes: CodeHashes,
+    chain: ChainConfig,
+    enigma: &dyn Enigma,
+    address: String,
+    token: &ContractInfo,
+) -> TokenBalance {
+    let contract_address = token.contract_address.clone();
+    // a permit covering the token wins, since it needs no request to the wallet
+    let permit = keplr.permit(chain.chain_id, &contract_address);
+    let viewing_key = match permit {
+        Some(_) => None,
+        None => keplr.viewing_key(chain.chain_id, &contract_address).await,
+    };
+    if permit.is_none() && viewing_key.is_none() {
+        return TokenBalance::Locked;
+    }
+
+    let code_hash = match code_hashes.resolve(&contract_address).await {
+        Ok(code_hash) => code_hash,
+        Err(error) => return TokenBalance::Unknown(error.to_string()),
+    };
+
+    let result: Result<BalanceResponse, Error> = match (permit, viewing_key) {
+        (Some(permit), _) => {
+            query_with_permit(
+                wasm_client,
+                enigma,
+                &contract_address,
+                &code_hash,
+                &permit,
+                Snip20QueryWithPermit::Balance {},
+            )
+            .await
+        }
+        (None, key) => {
+            let query = QueryMsg::Balance {
+                address,
+                key: key.unwrap_or_default(),
+            };
+            query_contract(wasm_client, enigma, &contract_address, &code_hash, &query).await
+        }
+    };
+
+    match result {
+        Ok(response) => {
+            TokenBalance::Balance(Coin::from_snip20(response.balance.amount.u128(), token))
+        }
+        Err(error) => {
+            debug!("balance of {} failed: {error}", token.metadata.symbol);
+            TokenBalance::Unknown(error.to_string())
+        }
+    }
+}
+
+#[component]
+pub fn Portfolio() -> impl IntoView {
+    info!("rendering <Portfolio/>");
+
+    let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+    let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
+    let token_map = use_context::<Memo<TokenMap>>().expect("tokens context missing!");
+    let code_hashes = use_context::<CodeHashes>().expect("code hashes context missing!");
+    let tx_tracker = use_context::<TxTracker>().expect("tx tracker context missing!");
+
+    // contract addresses of the locked tokens the user picked for key setup
+    let selected = RwSignal::new(HashSet::<String>::new());
+    // the token whose transfer form is open. The form lives outside the balance rows, which are
+    // rebuilt on every reload, so it keeps its state when a transfer refreshes the balances.
+    let transferring = RwSignal::new(None::<ContractInfo>);
+    let reload = Trigger::new();
+
+    let balances = LocalResource::new(move || {
+        let key = keplr.key.get();
+        let tokens = token_map.get();
+        let chain = chain.get();
+        wasm_client.refresh.track();
+        reload.track();
+
+        SendWrapper::new(async move {
+            let Some(Ok(key)) = key else {
+                return vec![];
+            };
+            debug!("querying {} token balances", tokens.len());
+
+            // balance queries are encrypted with the wallet's seed, like the ones the wallet makes
+            let enigma = match keplr
+                .wallet
+                .get_untracked()
+                .provider()
+                .get_enigma_utils(chain.chain_id)
+            {
+                Ok(enigma) => KeplrEnigmaUtils::from(enigma),
+                Err(error) => {
+                    let error = error.to_string();
+                    return tokens
+                        .values()
+                        .map(|token| (token.clone(), TokenBalance::Unknown(error.clone())))
+                        .collect();
+                }
+            };
+            let enigma = &enigma;
+
+            let mut balances = futures::future::join_all(tokens.values().map(|token| {
+                let address = key.bech32_address.clone();
+                async move {
+                    let balance = token_balance(
+                        keplr,
+                        wasm_client,
+                        code_hashes,
+                        chain,
+                        enigma,
+                        address,
+                        token,
+                    )
+                    .await;
+                    (token.clone(), balance)
+                }
+            }))
+            .await;
+
+            // tokens with a balance first, then alphabetically
+            balances.sort_by_key(|(token, balance)| {
+                (
+                    !matches!(balance, TokenBalance::Balance(_)),
+                    token.metadata.name.to_lowercase(),
+                )
+            });
+            balances
+        })
+    });
+
+    // the method picked, while the user reviews the fee
+    let review_method = RwSignal::new(None::<ViewingKeyMethod>);
+    let fee_choice = RwSignal::new(FeeChoice::default());
+    let setup_gas_limit =
+        Signal::derive(move || viewing_keys::gas_limit(selected.with(HashSet::len)));
+
+    let setup_keys_action: Action<ViewingKeyMethod, Result<usize, Error>, SyncStorage> =
+        Action::new_unsync(move |method: &ViewingKeyMethod| {
+            let method = *method;
+            let fee = fee_choice.get_untracked();
+            let chain = chain.get_untracked();
+            let contracts = selected.get_untracked();
+            let tokens: Vec<ContractInfo> = token_map.with_untracked(|tokens| {
+                tokens
+                    .values()
+                    .filter(|token| contracts.contains(&token.contract_address))
+                    .cloned()
+                    .collect()
+            });
+
+            async move {
+                let contract_addresses: Vec<String> = tokens
+                    .iter()
+                    .map(|token| token.contract_address.clone())
+                    .collect();
+                let code_hashes = code_hashes.resolve_all(&contract_addresses).await?;
+
+                let client = SigningClient::new(keplr, wasm_client, chain).await?;
+                let keys = setup_viewing_keys(
+                    &client,
+                    keplr,
+                    tx_tracker,
+                    &tokens,
+                    &code_hashes,
+                    method,
+                    fee,
+                )
+                .await
+                .inspect_err(|error| error!("{error}"))?;
+
+                selected.set(HashSet::new());
+                reload.notify();
+                Ok(keys.len())
+            }
+        });
+
+    // one signature unlocks the balance of every token in the registry
+    let sign_permit_action: Action<(), Result<(), Error>, SyncStorage> =
+        Action::new_unsync(move |_: &()| {
+            let chain = chain.get_untracked();
+            let tokens = token_map.get_untracked();
+            async move {
+                keplr
+                    .sign_permit(chain.chain_id, &tokens)
+                    .await
+                    .inspect_err(|error| error!("{error}"))?;
+                reload.notify();
+                Ok(())
+            }
+        });
+    let permit_error = move || {
+        sign_permit_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| error.to_string())
+    };
+
+    let setup_result = move || match setup_keys_action.value().get() {
+        Some(Ok(count)) => format!("Set up {count} viewing keys."),
+        Some(Err(error)) => error.to_string(),
+        None => String::new(),
+    };
+    let nothing_selected = move || selected.with(HashSet::is_empty);
+    let setup_disabled = move || nothing_selected() || setup_keys_action.pending().get();
+    let confirm_setup = move |_| {
+        if let Some(method) = review_method.get_untracked() {
+            setup_keys_action.dispatch(method);
+        }
+        review_method.set(None);
+    };
+
+    let rows = move || {
+        Suspend::new(async move {
+            balances
+                .await
+                .into_iter()
+                .map(|(token, balance)| {
+                    let locked = matches!(balance, TokenBalance::Locked);
+                    let transferable = matches!(balance, TokenBalance::Balance(_));
+                    let contract_address = token.contract_address.clone();
+                    let toggle = move |_| {
+                        let contract_address = contract_address.clone();
+                        selected.update(|selected| {
+                            if !selected.remove(&contract_address) {
+                                selected.insert(contract_address);
+                            }
+                        })
+                    };
+                    let (amount, title) = match balance {
+                        TokenBalance::Balance(coin) => (coin.to_string(), String::new()),
+                        TokenBalance::Locked => (
+                            "Locked".to_string(),
+                            "No viewing key or permit for this token".to_string(),
+                        ),
+                        TokenBalance::Unknown(error) => ("Unknown".to_string(), error),
+                    };
+                    let transfer_button = transferable.then(|| {
+                        let token = token.clone();
+                        view! {
+                            <button
+                                class="text-sm"
+                                on:click=move |_| transferring.set(Some(token.clone()))
+                            >
+                                "Transfer"
+                            </button>
+                        }
+                    });
+                    view! {
+                        <li class="flex flex-col gap-2">
+                            <div class="flex items-center gap-4">
+                                <img
+                                    src=token.image_url
+                                    alt=token.metadata.symbol.clone()
+                                    class="h-8 w-8 rounded-full"
+                                />
+                                <span>{token.metadata.name}</span>
+                                <span class="ml-auto font-mono" title=title>
+                                    {amount}
+                                </span>
+                                {transfer_button}
+                                <input
+                                    type="checkbox"
+                                    title="Set up a viewing key"
+                                    disabled=!locked
+                                    on:change=toggle
+                                />
+                            </div>
+                        </li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let transfer_form = move || {
+        transferring.get().map(|token| {
+            view! {
+                <section class="flex flex-col gap-2">
+                    <div class="flex justify-between items-center gap-4">
+                        <h3>"Transfer " {token.metadata.symbol.clone()}</h3>
+                        <button on:click=move |_| transferring.set(None)>"Close"</button>
+                    </div>
+                    <TokenTransfer token=token on_confirmed=reload />
+                </section>
+            }
+        })
+    };
+
+    view! {
+        <Show
+            when=move || keplr.enabled.get()
+            fallback=|| view! { <p>"Connect a wallet to see your tokens."</p> }
+        >
+            <h2>"Portfolio"</h2>
+            <div class="flex gap-4 items-center">
+                <button
+                    on:click=move |_| review_method.set(Some(ViewingKeyMethod::Create))
+                    disabled=setup_disabled
+                >
+                    "Create Viewing Keys"
+                </button>
+                <button
+                    on:click=move |_| review_method.set(Some(ViewingKeyMethod::Set))
+                    disabled=setup_disabled
+                >
+                    "Set Viewing Keys"
+                </button>
+                <p class="text-sm">{setup_result}</p>
+            </div>
+            <Show when=move || review_method.get().is_some()>
+                <dialog open class="flex flex-col gap-4">
+                    <p>
+                        "Set up viewing keys for " {move || selected.with(HashSet::len)}
+                        " tokens"
+                    </p>
+                    <FeeSelector gas_limit=setup_gas_limit selected=fee_choice />
+                    <div class="flex gap-4">
+                        <button on:click=move |_| review_method.set(None)>"Cancel"</button>
+                        <button on:click=confirm_setup>"Confirm"</button>
+                    </div>
+                </dialog>
+            </Show>
+            <div class="flex gap-4 items-center">
+                <button
+                    on:click=move |_| _ = sign_permit_action.dispatch(())
+                    disabled=sign_permit_action.pending()
+                >
+                    "Sign Permit"
+                </button>
+                <p class="text-sm">{permit_error}</p>
+            </div>
+            <Suspense fallback=move || view! { <p>"Loading balances..."</p> }>
+                <ul class="flex flex-col gap-2">{rows}</ul>
+            </Suspense>
+            {transfer_form}
+        </Show>
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    coin::{Coin, DenomInfo},
+    components::FeeSelector,
+    config::ChainConfig,
+    error::Error,
+    fee::{estimate_gas, FeeChoice, FeeOption, DEFAULT_GAS_MULTIPLIER},
+    state::{KeplrSignals, WasmClient},
+    tx::{EncodedMsg, SigningClient},
+    tx_tracker::{TxResult, TxTracker},
+};
+use leptos::prelude::*;
+use secretrs::AccountId;
+use tracing::{debug, error, info};
+
+#[derive(Clone, Debug, PartialEq)]
+struct SendRequest {
+    recipient: String,
+    amount: String,
+    memo: String,
+}
+
+/// Checks that `address` is a valid account on the chain with `bech32_prefix`.
+pub fn validate_address(address: &str, bech32_prefix: &str) -> Result<(), Error> {
+    let account_id = address
+        .parse::<AccountId>()
+        .map_err(|_| Error::generic(format!("{address:?} is not a valid address")))?;
+
+    match account_id.prefix() == bech32_prefix {
+        true => Ok(()),
+        false => Err(Error::generic(format!(
+            "{address:?} is not a {bech32_prefix} address"
+        ))),
+    }
+}
+
+impl SendRequest {
+    fn msgs(&self, client: &SigningClient) -> Result<(Vec<EncodedMsg>, Coin), Error> {
+        let chain = client.chain();
+        let recipient = self.recipient.trim();
+        validate_address(recipient, chain.bech32_prefix)?;
+        let coin = Coin::parse(&self.amount, &DenomInfo::from(chain))?;
+        if coin.amount.is_zero() {
+            return Err(Error::generic("amount must be more than zero"));
+        }
+
+        Ok((vec![client.send_msg(recipient, &[coin.clone()])?], coin))
+    }
+}
+
+#[component]
+pub fn SendTokens() -> impl IntoView {
+    info!("rendering <SendTokens/>");
+
+    let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+    let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
+    let tx_tracker = use_context::<TxTracker>().expect("tx tracker context missing!");
+
+    let recipient = RwSignal::new(String::new());
+    let amount = RwSignal::new(String::new());
+    let memo = RwSignal::new(String::new());
+
+    let fee_choice = RwSignal::new(FeeChoice::default());
+
+    // simulates the transaction, so the user can pick a fee before signing
+    let review_action: Action<SendRequest, Result<(SendRequest, u64), Error>, SyncStorage> =
+        Action::new_unsync(move |request: &SendRequest| {
+            let request = request.clone();
+            let chain = chain.get_untracked();
+            async move {
+                let client = SigningClient::new(keplr, wasm_client, chain).await?;
+                let (msgs, _) = request.msgs(&client)?;
+                let gas_limit =
+                    estimate_gas(&client, &msgs, &request.memo, DEFAULT_GAS_MULTIPLIER).await?;
+                Ok((request, gas_limit))
+            }
+        });
+    let review = move || review_action.value().get().and_then(Result::ok);
+    let gas_limit =
+        Signal::derive(move || review().map(|(_, gas_limit)| gas_limit).unwrap_or_default());
+    let review_error = move || {
+        review_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| error.to_string())
+    };
+
+    let send_action: Action<(SendRequest, FeeOption), Result<TxResult, Error>, SyncStorage> =
+        Action::new_unsync(move |(request, fee): &(SendRequest, FeeOption)| {
+            let (request, fee) = (request.clone(), fee.clone());
+            let chain = chain.get_untracked();
+            async move {
+                let client = SigningClient::new(keplr, wasm_client, chain).await?;
+                let (msgs, coin) = request.msgs(&client)?;
+                debug!("sending {coin} to {} for {}", request.recipient, fee.amount);
+
+                let response = client
+                    .sign_and_track(
+                        &msgs,
+                        &fee.tx_options(request.memo),
+                        tx_tracker,
+                        format!("Send {coin}"),
+                    )
+                    .await
+                    .inspect_err(|error| error!("{error}"))?;
+
+                // balances changed
+                wasm_client.refresh.notify();
+
+                Ok(TxResult::from(&response))
+            }
+        });
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        review_action.dispatch(SendRequest {
+            recipient: recipient.get_untracked(),
+            amount: amount.get_untracked(),
+            memo: memo.get_untracked(),
+        });
+    };
+
+    let close_review = move || review_action.value().set(None);
+    let confirm = move |_| {
+        let Some((request, gas_limit)) = review() else {
+            return;
+        };
+        if let Some(fee) = fee_choice
+            .get_untracked()
+            .fee(&chain.get_untracked(), gas_limit)
+        {
+            send_action.dispatch((request, fee));
+        }
+        close_review();
+    };
+
+    let result = move || {
+        send_action.value().get().map(|result| match result {
+            Ok(result) => {
+                let explorer = chain.get_untracked().tx_url(&result.tx_hash);
+                view! {
+                    <div class="text-sm">
+                        <p>"Sent in block " {result.height}</p>
+                        <p class="font-mono break-all">
+                            {match explorer {
+                                Some(url) => {
+                                    view! {
+                                        <a href=url target="_blank">
+                                            {result.tx_hash}
+                                        </a>
+                                    }
+                                        .into_any()
+                                }
+                                None => result.tx_hash.into_any(),
+                            }}
+                        </p>
+                        <p>"Gas used: " {result.gas_used} " / " {result.gas_wanted}</p>
+                    </div>
+                }
+                .into_any()
+            }
+            Err(error) => {
+                view! { <p class="text-sm text-red-500">{error.to_string()}</p> }.into_any()
+            }
+        })
+    };
+
+    view! {
+        <Show
+            when=move || keplr.enabled.get()
+            fallback=|| view! { <p>"Connect a wallet to send tokens."</p> }
+        >
+            <h2>"Send " {move || chain.get().display_denom}</h2>
+            <form class="flex flex-col gap-4" on:submit=on_submit>
+                <label class="flex flex-col gap-1">
+                    "Recipient"
+                    <input
+                        type="text"
+                        placeholder=move || format!("{}1...", chain.get().bech32_prefix)
+                        prop:value=recipient
+                        on:input=move |ev| recipient.set(event_target_value(&ev))
+                    />
+                </label>
+                <label class="flex flex-col gap-1">
+                    "Amount"
+                    <input
+                        type="text"
+                        inputmode="decimal"
+                        placeholder="0.0"
+                        prop:value=amount
+                        on:input=move |ev| amount.set(event_target_value(&ev))
+                    />
+                </label>
+                <label class="flex flex-col gap-1">
+                    "Memo"
+                    <input
+                        type="text"
+                        prop:value=memo
+                        on:input=move |ev| memo.set(event_target_value(&ev))
+                    />
+                </label>
+                <input
+                    type="submit"
+                    value="Review"
+                    disabled=move || review_action.pending().get() || send_action.pending().get()
+                />
+            </form>
+            <p class="text-sm text-red-500">{review_error}</p>
+            <Show when=move || review().is_some()>
+                <dialog open class="flex flex-col gap-4">
+                    <p>
+                        "Send " {move || review().map(|(request, _)| request.amount)} " "
+                        {move || chain.get().display_denom} " to "
+                        <span class="font-mono break-all">
+                            {move || review().map(|(request, _)| request.recipient)}
+                        </span>
+                    </p>
+                    <FeeSelector gas_limit=gas_limit selected=fee_choice />
+                    <div class="flex gap-4">
+                        <button on:click=move |_| close_review()>"Cancel"</button>
+                        <button on:click=confirm>"Confirm"</button>
+                    </div>
+                </dialog>
+            </Show>
+            <Show when=move || send_action.pending().get()>
+                <p class="text-sm">"Waiting for the transaction..."</p>
+            </Show>
+            {result}
+        </Show>
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{
+    code_hash::CodeHashes,
+    coin::Coin,
+    components::{validate_address, FeeSelector},
+    config::ChainConfig,
+    error::Error,
+    fee::{estimate_gas, FeeChoice, FeeOption, DEFAULT_GAS_MULTIPLIER},
+    keplr::tokens::ContractInfo,
+    snip20::{parse_amount, send_msg, transfer_msg, SendCallback},
+    state::{KeplrSignals, WasmClient},
+    tx::{EncodedMsg, SigningClient},
+    tx_tracker::{TxResult, TxTracker},
+};
+use leptos::prelude::*;
+use tracing::{debug, error};
+
+#[derive(Clone, Debug, PartialEq)]
+struct TransferRequest {
+    recipient: String,
+    amount: String,
+    memo: String,
+    /// JSON for the recipient contract. Empty means a plain `transfer`.
+    callback: String,
+}
+
+impl TransferRequest {
+    async fn msgs(
+        &self,
+        client: &SigningClient,
+        code_hashes: CodeHashes,
+        token: &ContractInfo,
+    ) -> Result<(Vec<EncodedMsg>, Coin), Error> {
+        let recipient = self.recipient.trim();
+        validate_address(recipient, client.chain().bech32_prefix)?;
+        let coin = parse_amount(&self.amount, token)?;
+        let callback = match self.callback.trim() {
+            "" => None,
+            msg => Some(SendCallback {
+                code_hash: code_hashes.resolve(recipient).await?,
+                msg: serde_json::from_str(msg)
+                    .map_err(|e| Error::generic(format!("invalid callback msg: {e}")))?,
+            }),
+        };
+
+        let code_hash = code_hashes.resolve(&token.contract_address).await?;
+        let msg = match callback {
+            Some(callback) => {
+                send_msg(
+                    client,
+                    token,
+                    &code_hash,
+                    recipient,
+                    &coin,
+                    &self.memo,
+                    Some(callback),
+                )
+                .await?
+            }
+            None => transfer_msg(client, token, &code_hash, recipient, &coin, &self.memo).await?,
+        };
+        Ok((vec![msg], coin))
+    }
+}
+
+/// A transfer form for one token. `on_confirmed` is notified once the transaction is in a block.
+#[component]
+pub fn TokenTransfer(token: ContractInfo, on_confirmed: Trigger) -> impl IntoView {
+    let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+    let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
+    let code_hashes = use_context::<CodeHashes>().expect("code hashes context missing!");
+    let tx_tracker = use_context::<TxTracker>().expect("tx tracker context missing!");
+
+    let recipient = RwSignal::new(String::new());
+    let amount = RwSignal::new(String::new());
+    let memo = RwSignal::new(String::new());
+    let callback = RwSignal::new(String::new());
+
+    let symbol = token.metadata.symbol.clone();
+    let review_symbol = symbol.clone();
+
+    let fee_choice = RwSignal::new(FeeChoice::default());
+
+    // simulates the transaction, so the user can pick a fee before signing
+    let review_action: Action<TransferRequest, Result<(TransferRequest, u64), Error>, SyncStorage> =
+        Action::new_unsync({
+            let token = token.clone();
+            move |request: &TransferRequest| {
+                let request = request.clone();
+                let token = token.clone();
+                let chain = chain.get_untracked();
+                async move {
+                    let client = SigningClient::new(keplr, wasm_client, chain).await?;
+                    let (msgs, _) = request.msgs(&client, code_hashes, &token).await?;
+                    let gas_limit =
+                        estimate_gas(&client, &msgs, "", DEFAULT_GAS_MULTIPLIER).await?;
+                    Ok((request, gas_limit))
+                }
+            }
+        });
+    let review = move || review_action.value().get().and_then(Result::ok);
+    let gas_limit =
+        Signal::derive(move || review().map(|(_, gas_limit)| gas_limit).unwrap_or_default());
+    let review_error = move || {
+        review_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| error.to_string())
+    };
+
+    let transfer_action: Action<
+        (TransferRequest, FeeOption),
+        Result<TxResult, Error>,
+        SyncStorage,
+    > = Action::new_unsync(move |(request, fee): &(TransferRequest, FeeOption)| {
+        let (request, fee) = (request.clone(), fee.clone());
+        let token = token.clone();
+        let chain = chain.get_untracked();
+        async move {
+            let client = SigningClient::new(keplr, wasm_client, chain).await?;
+            let (msgs, coin) = request.msgs(&client, code_hashes, &token).await?;
+            debug!(
+                "transferring {coin} to {} for {}",
+                request.recipient, fee.amount
+            );
+
+            let response = client
+                .sign_and_track(
+                    &msgs,
+                    &fee.tx_options(""),
+                    tx_tracker,
+                    format!("Transfer {coin}"),
+                )
+                .await
+                .inspect_err(|error| error!("{error}"))?;
+
+            on_confirmed.notify();
+
+            Ok(TxResult::from(&response))
+        }
+    });
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        review_action.dispatch(TransferRequest {
+            recipient: recipient.get_untracked(),
+            amount: amount.get_untracked(),
+            memo: memo.get_untracked(),
+            callback: callback.get_untracked(),
+        });
+    };
+
+    let close_review = move || review_action.value().set(None);
+    let confirm = move |_| {
+        let Some((request, gas_limit)) = review() else {
+            return;
+        };
+        if let Some(fee) = fee_choice
+            .get_untracked()
+            .fee(&chain.get_untracked(), gas_limit)
+        {
+            transfer_action.dispatch((request, fee));
+        }
+        close_review();
+    };
+
+    let result = move || {
+        transfer_action.value().get().map(|result| match result {
+            Ok(result) => view! {
+                <p class="text-sm font-mono break-all">
+                    "Confirmed in block " {result.height} ": " {result.tx_hash}
+                </p>
+            }
+            .into_any(),
+            Err(error) => {
+                view! { <p class="text-sm text-red-500">{error.to_string()}</p> }.into_any()
+            }
+        })
+    };
+
+    view! {
+        <form class="flex flex-col gap-2" on:submit=on_submit>
+            <input
+                type="text"
+                placeholder="Recipient"
+                prop:value=recipient
+                on:input=move |ev| recipient.set(event_target_value(&ev))
+            />
+            <input
+                type="text"
+                inputmode="decimal"
+                placeholder=format!("Amount ({symbol})")
+                prop:value=amount
+                on:input=move |ev| amount.set(event_target_value(&ev))
+            />
+            <input
+                type="text"
+                placeholder="Memo"
+                prop:value=memo
+                on:input=move |ev| memo.set(event_target_value(&ev))
+            />
+            <textarea
+                placeholder="Message for a receiving contract (optional JSON)"
+                prop:value=callback
+                on:input=move |ev| callback.set(event_target_value(&ev))
+            />
+            <input
+                type="submit"
+                value="Review"
+                disabled=move || review_action.pending().get() || transfer_action.pending().get()
+            />
+        </form>
+        <p class="text-sm text-red-500">{review_error}</p>
+        <Show when=move || review().is_some()>
+            <dialog open class="flex flex-col gap-4">
+                <p>
+                    {move || {
+                        review()
+                            .map(|(request, _)| match request.callback.trim().is_empty() {
+                                true => "Transfer ",
+                                false => "Send ",
+                            })
+                    }} {move || review().map(|(request, _)| request.amount)} " "
+                    {review_symbol.clone()} " to "
+                    <span class="font-mono break-all">
+                        {move || review().map(|(request, _)| request.recipient)}
+                    </span>
+                </p>
+                <FeeSelector gas_limit=gas_limit selected=fee_choice />
+                <div class="flex gap-4">
+                    <button on:click=move |_| close_review()>"Cancel"</button>
+                    <button on:click=confirm>"Confirm"</button>
+                </div>
+            </dialog>
+        </Show>
+        <Show when=move || transfer_action.pending().get()>
+            <p class="text-sm">"Waiting for the transaction..."</p>
+        </Show>
+        {result}
+    }
+}
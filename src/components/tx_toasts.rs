@@ -0,0 +1,71 @@
+use crate::{
+    config::ChainConfig,
+    tx_tracker::{TrackedTx, TxStatus, TxTracker},
+};
+use leptos::prelude::*;
+
+fn status_text(status: &TxStatus) -> String {
+    match status {
+        TxStatus::Pending => "Waiting for a block...".to_string(),
+        TxStatus::Included(result) if result.is_success() => {
+            format!("Confirmed in block {}", result.height)
+        }
+        TxStatus::Included(result) => format!(
+            "Failed with {} code {}: {}",
+            result.codespace, result.code, result.log
+        ),
+        TxStatus::TimedOut => "Not included in time, it may still go through".to_string(),
+    }
+}
+
+/// Progress of the transactions followed by [`TxTracker`], in the corner of the screen.
+#[component]
+pub fn TxToasts() -> impl IntoView {
+    let tracker = use_context::<TxTracker>().expect("tx tracker context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+
+    // pending txs on top, as they're the ones still changing
+    let visible = move || {
+        tracker
+            .pending
+            .get()
+            .into_iter()
+            .chain(tracker.finished.get())
+            .filter(|tx| !tx.dismissed)
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <ul class="fixed bottom-4 right-4 flex flex-col gap-2 max-w-sm">
+            <For
+                each=visible
+                key=|tx| (tx.tx_hash.clone(), tx.status.clone())
+                children=move |tx: TrackedTx| {
+                    let TrackedTx { tx_hash, description, status, .. } = tx;
+                    let explorer = chain.get_untracked().tx_url(&tx_hash);
+                    let dismiss = {
+                        let tx_hash = tx_hash.clone();
+                        move |_| tracker.dismiss(&tx_hash)
+                    };
+                    view! {
+                        <li class="flex flex-col gap-1 p-2 text-sm bg-neutral-800 rounded-sm">
+                            <div class="flex justify-between items-center gap-4">
+                                <strong>{description}</strong>
+                                <button on:click=dismiss>"Dismiss"</button>
+                            </div>
+                            <p>{status_text(&status)}</p>
+                            {explorer
+                                .map(|url| {
+                                    view! {
+                                        <a href=url target="_blank">
+                                            "View transaction"
+                                        </a>
+                                    }
+                                })}
+                        </li>
+                    }
+                }
+            />
+        </ul>
+    }
+}
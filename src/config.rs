@@ -0,0 +1,178 @@
+//! The networks the app can connect to.
+
+use crate::{
+    error::Error,
+    fee::FeeDenom,
+    keplr::{
+        self,
+        suggest_chain_types::{ChainInfoBuilder, GasPriceStep},
+        Wallet,
+    },
+    storage,
+};
+use rsecret::secret_network_client::CreateQuerierOptions;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Pulsar,
+    LocalSecret,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::Mainnet, Network::Pulsar, Network::LocalSecret];
+
+    const STORAGE_KEY: &'static str = "secret-leptos:network";
+
+    pub fn config(&self) -> ChainConfig {
+        match self {
+            Network::Mainnet => ChainConfig {
+                network: *self,
+                chain_id: "secret-4",
+                grpc_urls: &[
+                    "https://grpc.mainnet.secretsaturn.net",
+                    "https://secretnetwork-grpc.lavenderfive.com",
+                    "https://grpc.secret.adrius.starshell.net",
+                ],
+                lcd_url: "https://lcd.mainnet.secretsaturn.net",
+                rpc_url: "https://rpc.mainnet.secretsaturn.net",
+                explorer_tx_url: Some("https://www.mintscan.io/secret/tx/{txHash}"),
+                ..ChainConfig::SCRT
+            },
+            Network::Pulsar => ChainConfig {
+                network: *self,
+                chain_id: "pulsar-3",
+                grpc_urls: &[
+                    "https://grpc.pulsar.scrttestnet.com",
+                    "https://grpc.testnet.secretsaturn.net",
+                ],
+                lcd_url: "https://api.pulsar.scrttestnet.com",
+                rpc_url: "https://rpc.pulsar.scrttestnet.com",
+                explorer_tx_url: Some("https://testnet.ping.pub/secret/tx/{txHash}"),
+                ..ChainConfig::SCRT
+            },
+            Network::LocalSecret => ChainConfig {
+                network: *self,
+                chain_id: "secretdev-1",
+                grpc_urls: &["http://127.0.0.1:9091"],
+                lcd_url: "http://127.0.0.1:1317",
+                rpc_url: "http://127.0.0.1:26657",
+                explorer_tx_url: None,
+                ..ChainConfig::SCRT
+            },
+        }
+    }
+
+    /// Wallets ship with mainnet; every other network has to be suggested before enabling it.
+    pub fn is_builtin(&self) -> bool {
+        matches!(self, Network::Mainnet)
+    }
+
+    /// The network picked on a previous visit, or mainnet.
+    pub fn load() -> Self {
+        storage::load(Self::STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::STORAGE_KEY, self)
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "Mainnet"),
+            Network::Pulsar => write!(f, "Pulsar (testnet)"),
+            Network::LocalSecret => write!(f, "LocalSecret"),
+        }
+    }
+}
+
+/// Everything that differs between networks. Provided as a `RwSignal<ChainConfig>` context.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainConfig {
+    pub network: Network,
+    pub chain_id: &'static str,
+    /// gRPC-web endpoints, in order of preference.
+    pub grpc_urls: &'static [&'static str],
+    pub lcd_url: &'static str,
+    pub rpc_url: &'static str,
+    /// Base denom used for fees and balances.
+    pub denom: &'static str,
+    pub display_denom: &'static str,
+    pub decimals: u8,
+    pub bech32_prefix: &'static str,
+    /// Currencies fees can be paid in, the preferred one first.
+    pub fee_denoms: &'static [FeeDenom],
+    /// Url with a `{txHash}` placeholder.
+    pub explorer_tx_url: Option<&'static str>,
+}
+
+impl ChainConfig {
+    const SCRT: ChainConfig = ChainConfig {
+        network: Network::Mainnet,
+        chain_id: "",
+        grpc_urls: &[],
+        lcd_url: "",
+        rpc_url: "",
+        denom: "uscrt",
+        display_denom: "SCRT",
+        decimals: 6,
+        bech32_prefix: "secret",
+        fee_denoms: &[FeeDenom {
+            denom: "uscrt",
+            symbol: "SCRT",
+            decimals: 6,
+            gas_price_step: GasPriceStep::SCRT,
+        }],
+        explorer_tx_url: None,
+    };
+
+    pub fn tx_url(&self, tx_hash: &str) -> Option<String> {
+        self.explorer_tx_url
+            .map(|url| url.replace("{txHash}", tx_hash))
+    }
+
+    /// Options for rsecret's queriers. Queries go through the [`WasmClient`](crate::state::WasmClient)
+    /// they're given, so the url here is only nominal.
+    pub fn querier_options(&self) -> Result<CreateQuerierOptions, Error> {
+        let encryption_utils = secretrs::EncryptionUtils::new(None, self.chain_id)
+            .map_err(|e| Error::Secret(e.to_string()))?;
+        Ok(CreateQuerierOptions {
+            url: self.grpc_urls.first().copied().unwrap_or_default(),
+            chain_id: self.chain_id,
+            encryption_utils,
+        })
+    }
+
+    /// Enables this chain in `wallet`, suggesting it first if the wallet doesn't know it.
+    pub async fn enable(&self, wallet: Wallet) -> Result<(), keplr::Error> {
+        let provider = wallet.provider();
+
+        if !self.network.is_builtin() {
+            let builder = match self.network {
+                Network::Pulsar => ChainInfoBuilder::pulsar(),
+                _ => ChainInfoBuilder::localsecret(),
+            };
+            let chain_info = builder
+                .rpc(self.rpc_url)
+                .rest(self.lcd_url)
+                .bech32_prefix(self.bech32_prefix)
+                .build()?;
+            debug!("suggesting {} to {wallet}", self.chain_id);
+            provider.suggest_chain(chain_info).await?;
+        }
+
+        provider.enable(vec![self.chain_id.to_string()]).await
+    }
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Network::default().config()
+    }
+}
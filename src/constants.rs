@@ -1,5 +0,0 @@
-#![allow(unused)]
-
-pub static CHAIN_ID: &str = "secret-4";
-pub static LCD_URL: &str = "https://lcd.mainnet.secretsaturn.net";
-pub static GRPC_URL: &str = "https://grpc.mainnet.secretsaturn.net";
@@ -0,0 +1,71 @@
+//! Typed, encrypted contract queries.
+
+use crate::{
+    enigma::{self, Enigma, NONCE_LEN},
+    error::Error,
+    state::WasmClient,
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use secretrs::proto::secret::compute::v1beta1::{
+    query_client::QueryClient as ComputeQueryClient, QuerySecretContractRequest,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::debug;
+
+/// Sends `query` to the contract, encrypted with `enigma`, and decrypts the answer.
+///
+/// Contract errors come back encrypted too; they're decrypted into [`Error::Secret`].
+pub async fn query_contract<Q: Serialize, R: DeserializeOwned>(
+    wasm_client: WasmClient,
+    enigma: &dyn Enigma,
+    contract_address: &str,
+    code_hash: &str,
+    query: &Q,
+) -> Result<R, Error> {
+    let query = serde_json::to_value(query).map_err(Error::generic)?;
+    let encrypted = enigma.encrypt(code_hash, &query).await?;
+    let nonce = enigma::nonce(&encrypted)?;
+
+    debug!("querying {contract_address}");
+    let result = wasm_client
+        .query(|client| {
+            let request = QuerySecretContractRequest {
+                contract_address: contract_address.to_string(),
+                query: encrypted.clone(),
+            };
+            async move {
+                ComputeQueryClient::new(client)
+                    .query_secret_contract(request)
+                    .await
+            }
+        })
+        .await;
+
+    let data = match result {
+        Ok(response) => response.into_inner().data,
+        Err(status) => return Err(decrypt_error(enigma, status.message(), &nonce).await),
+    };
+
+    // the contract base64 encodes its answer before the enclave encrypts it
+    let decrypted = enigma.decrypt(&data, &nonce).await?;
+    let answer = BASE64_STANDARD.decode(&decrypted).map_err(Error::generic)?;
+
+    serde_json::from_slice(&answer).map_err(Error::generic)
+}
+
+/// Errors raised by the contract look like `... encrypted: <base64>: ...`. Anything else is an
+/// error from the node and is passed on as is.
+async fn decrypt_error(enigma: &dyn Enigma, message: &str, nonce: &[u8; NONCE_LEN]) -> Error {
+    let ciphertext = message
+        .split_once("encrypted: ")
+        .map(|(_, rest)| rest.split(':').next().unwrap_or(rest).trim())
+        .and_then(|ciphertext| BASE64_STANDARD.decode(ciphertext).ok());
+
+    let Some(ciphertext) = ciphertext else {
+        return Error::Secret(message.to_string());
+    };
+    match enigma.decrypt(&ciphertext, nonce).await {
+        Ok(decrypted) => Error::Secret(String::from_utf8_lossy(&decrypted).into_owned()),
+        Err(_) => Error::Secret(message.to_string()),
+    }
+}
@@ -0,0 +1,159 @@
+//! Health checks for gRPC-web endpoints.
+
+use crate::{error::Error, storage};
+use rsecret::query::tendermint::TendermintQuerier;
+use std::time::Duration;
+use tonic_web_wasm_client::Client;
+use tracing::{debug, warn};
+use web_sys::{js_sys::Date, Url};
+
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Endpoints more than this many blocks behind the best one are considered out of sync.
+const MAX_BLOCKS_BEHIND: u64 = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointStatus {
+    pub url: String,
+    /// Latest block height, `None` if the endpoint didn't respond.
+    pub height: Option<u64>,
+    pub latency_ms: Option<f64>,
+}
+
+impl EndpointStatus {
+    pub fn unknown(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            height: None,
+            latency_ms: None,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.height.is_some()
+    }
+}
+
+/// Asks the endpoint for the latest block, timing the round trip.
+pub async fn probe(url: String) -> EndpointStatus {
+    let tendermint = TendermintQuerier::new(Client::new(url.clone()));
+    let start = Date::now();
+
+    match tendermint.get_latest_block().await {
+        Ok(response) => {
+            let latency_ms = Date::now() - start;
+            let height = response
+                .block
+                .and_then(|block| block.header)
+                .map(|header| header.height as u64);
+            debug!("{url}: height {height:?} in {latency_ms}ms");
+            EndpointStatus {
+                url,
+                height,
+                latency_ms: Some(latency_ms),
+            }
+        }
+        Err(error) => {
+            warn!("{url} is unreachable: {error}");
+            EndpointStatus::unknown(url)
+        }
+    }
+}
+
+/// The fastest endpoint among those that are in sync with the highest block seen.
+pub fn healthiest(statuses: &[EndpointStatus]) -> Option<&EndpointStatus> {
+    let best_height = statuses.iter().filter_map(|status| status.height).max()?;
+
+    statuses
+        .iter()
+        .filter(|status| {
+            status
+                .height
+                .is_some_and(|height| height + MAX_BLOCKS_BEHIND >= best_height)
+        })
+        .min_by(|a, b| {
+            let a = a.latency_ms.unwrap_or(f64::INFINITY);
+            let b = b.latency_ms.unwrap_or(f64::INFINITY);
+            a.total_cmp(&b)
+        })
+}
+
+/// Whether a failed query means the node couldn't be reached, as opposed to the node rejecting
+/// the query. Only the former is worth retrying on another endpoint.
+pub fn is_transport_error(error: &impl std::fmt::Display) -> bool {
+    let message = error.to_string();
+    [
+        "transport error",
+        "Failed to fetch",
+        "NetworkError",
+        "Unavailable",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Whether a failed request never reached the node, because no connection could be made. Only
+/// those are safe to retry for requests that mustn't run twice, like broadcasting a tx.
+pub fn is_connect_error(error: &impl std::fmt::Display) -> bool {
+    let message = error.to_string();
+    [
+        "error trying to connect",
+        "dns error",
+        "Connection refused",
+        "ERR_NAME_NOT_RESOLVED",
+        "ERR_CONNECTION_REFUSED",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Checks an endpoint the user entered: it has to be an http(s) url, respond, and serve the
+/// chain we're on.
+pub async fn validate(url: &str, chain_id: &str) -> Result<EndpointStatus, Error> {
+    let parsed = Url::new(url).map_err(|_| Error::generic(format!("{url:?} is not a url")))?;
+    if !matches!(parsed.protocol().as_str(), "http:" | "https:") {
+        return Err(Error::generic(format!("{url:?} is not an http(s) url")));
+    }
+    // drop any trailing slash, so the same endpoint isn't added to the pool twice
+    let url = url.trim_end_matches('/').to_string();
+
+    let tendermint = TendermintQuerier::new(Client::new(url.clone()));
+    let node_info = tendermint
+        .get_node_info()
+        .await
+        .map_err(|error| Error::generic(format!("{url} did not respond: {error}")))?;
+    let network = node_info
+        .default_node_info
+        .map(|info| info.network)
+        .unwrap_or_default();
+    if network != chain_id {
+        return Err(Error::generic(format!(
+            "{url} serves {network:?}, not {chain_id:?}"
+        )));
+    }
+
+    let status = probe(url).await;
+    match status.is_healthy() {
+        true => Ok(status),
+        false => Err(Error::generic(format!(
+            "{} did not return a block",
+            status.url
+        ))),
+    }
+}
+
+fn custom_storage_key(chain_id: &str) -> String {
+    format!("secret-leptos:endpoint:{chain_id}")
+}
+
+pub fn load_custom(chain_id: &str) -> Option<String> {
+    storage::load(&custom_storage_key(chain_id))
+}
+
+pub fn save_custom(chain_id: &str, url: &str) {
+    storage::save(&custom_storage_key(chain_id), &url)
+}
+
+pub fn forget_custom(chain_id: &str) {
+    storage::remove(&custom_storage_key(chain_id))
+}
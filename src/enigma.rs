@@ -0,0 +1,42 @@
+//! Encryption for contract messages.
+//!
+//! Secret contracts only accept messages encrypted for the chain's enclave, and answer with data
+//! encrypted the same way. [`Enigma`] abstracts over who holds the encryption seed: a throwaway
+//! [`EncryptionUtils`] for public queries, or the wallet's
+//! [`KeplrEnigmaUtils`](crate::keplr::KeplrEnigmaUtils) for anything tied to the user's account.
+
+use crate::error::Error;
+use async_trait::async_trait;
+use secretrs::EncryptionUtils;
+
+/// Length of the nonce that prefixes every encrypted message.
+pub const NONCE_LEN: usize = 32;
+
+#[async_trait(?Send)]
+pub trait Enigma {
+    /// Encrypts `msg` for the contract with `code_hash`. The result starts with the nonce,
+    /// followed by the public key of the seed, then the ciphertext.
+    async fn encrypt(&self, code_hash: &str, msg: &serde_json::Value) -> Result<Vec<u8>, Error>;
+
+    /// Decrypts a contract's answer to a message that was encrypted with `nonce`.
+    async fn decrypt(&self, ciphertext: &[u8], nonce: &[u8; NONCE_LEN]) -> Result<Vec<u8>, Error>;
+}
+
+#[async_trait(?Send)]
+impl Enigma for EncryptionUtils {
+    async fn encrypt(&self, code_hash: &str, msg: &serde_json::Value) -> Result<Vec<u8>, Error> {
+        EncryptionUtils::encrypt(self, code_hash, msg).map_err(|e| Error::Secret(e.to_string()))
+    }
+
+    async fn decrypt(&self, ciphertext: &[u8], nonce: &[u8; NONCE_LEN]) -> Result<Vec<u8>, Error> {
+        EncryptionUtils::decrypt(self, nonce, ciphertext).map_err(|e| Error::Secret(e.to_string()))
+    }
+}
+
+/// Splits the nonce off a message returned by [`Enigma::encrypt`].
+pub fn nonce(encrypted: &[u8]) -> Result<[u8; NONCE_LEN], Error> {
+    encrypted
+        .get(..NONCE_LEN)
+        .and_then(|nonce| nonce.try_into().ok())
+        .ok_or_else(|| Error::generic("encrypted message is too short"))
+}
@@ -13,6 +13,9 @@ pub enum Error {
 
     #[error("Keplr is not enabled!")]
     KeplrDisabled,
+
+    #[error(transparent)]
+    Amount(#[from] crate::coin::AmountError),
 }
 
 impl From<rsecret::Error> for Error {
@@ -0,0 +1,210 @@
+//! Gas estimation and fees.
+//!
+//! The gas limit comes from simulating the transaction, times a multiplier for headroom. The fee
+//! is that limit times the gas price of the chosen tier, in any of the chain's fee currencies.
+
+use crate::{
+    coin::{Amount, Coin, DenomInfo},
+    config::ChainConfig,
+    error::Error,
+    keplr::suggest_chain_types::GasPriceStep,
+    tx::{EncodedMsg, SigningClient, TxOptions},
+};
+use std::fmt;
+
+/// State can change between simulation and inclusion, so the simulated gas is multiplied by this.
+pub const DEFAULT_GAS_MULTIPLIER: f64 = 1.3;
+
+/// A currency the chain accepts fees in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeDenom {
+    pub denom: &'static str,
+    pub symbol: &'static str,
+    pub decimals: u8,
+    pub gas_price_step: GasPriceStep,
+}
+
+impl From<&FeeDenom> for DenomInfo {
+    fn from(fee_denom: &FeeDenom) -> Self {
+        Self {
+            denom: fee_denom.denom.to_string(),
+            symbol: fee_denom.symbol.to_string(),
+            decimals: fee_denom.decimals,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeeTier {
+    Low,
+    #[default]
+    Average,
+    High,
+}
+
+impl FeeTier {
+    pub const ALL: [FeeTier; 3] = [FeeTier::Low, FeeTier::Average, FeeTier::High];
+
+    pub fn gas_price(&self, step: &GasPriceStep) -> f64 {
+        match self {
+            FeeTier::Low => step.low,
+            FeeTier::Average => step.average,
+            FeeTier::High => step.high,
+        }
+    }
+}
+
+impl fmt::Display for FeeTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeTier::Low => write!(f, "Low"),
+            FeeTier::Average => write!(f, "Average"),
+            FeeTier::High => write!(f, "High"),
+        }
+    }
+}
+
+/// Which tier and currency the user picked. The amount follows from the gas limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeChoice {
+    pub tier: FeeTier,
+    /// `None` means the chain's first fee currency.
+    pub denom: Option<&'static str>,
+}
+
+impl FeeChoice {
+    pub fn fee(&self, chain: &ChainConfig, gas_limit: u64) -> Option<FeeOption> {
+        let fee_denom = match self.denom {
+            Some(denom) => chain.fee_denoms.iter().find(|fee| fee.denom == denom),
+            None => chain.fee_denoms.first(),
+        }?;
+        Some(FeeOption::new(self.tier, fee_denom, gas_limit))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeOption {
+    pub tier: FeeTier,
+    pub gas_limit: u64,
+    pub gas_price: f64,
+    pub amount: Coin,
+}
+
+impl FeeOption {
+    pub fn new(tier: FeeTier, fee_denom: &FeeDenom, gas_limit: u64) -> Self {
+        let gas_price = tier.gas_price(&fee_denom.gas_price_step);
+        Self {
+            tier,
+            gas_limit,
+            gas_price,
+            amount: Coin::new(
+                &fee_denom.into(),
+                Amount::new(fee_amount(gas_limit, gas_price), fee_denom.decimals),
+            ),
+        }
+    }
+
+    pub fn tx_options(&self, memo: impl Into<String>) -> TxOptions {
+        TxOptions {
+            gas_limit: self.gas_limit,
+            gas_price: self.gas_price,
+            fee_denom: Some(self.amount.denom.clone()),
+            memo: memo.into(),
+        }
+    }
+}
+
+/// Gas prices can be fractional; the fee is rounded up so it never falls below the minimum.
+pub fn fee_amount(gas_limit: u64, gas_price: f64) -> u128 {
+    (gas_limit as f64 * gas_price).ceil() as u128
+}
+
+pub fn gas_limit(gas_used: u64, multiplier: f64) -> u64 {
+    (gas_used as f64 * multiplier).ceil() as u64
+}
+
+/// Every tier in every fee currency of `chain`, with the choice that picks it.
+pub fn fee_options(chain: &ChainConfig, gas_limit: u64) -> Vec<(FeeChoice, FeeOption)> {
+    chain
+        .fee_denoms
+        .iter()
+        .flat_map(|fee_denom| {
+            FeeTier::ALL.into_iter().map(move |tier| {
+                let choice = FeeChoice {
+                    tier,
+                    denom: Some(fee_denom.denom),
+                };
+                (choice, FeeOption::new(tier, fee_denom, gas_limit))
+            })
+        })
+        .collect()
+}
+
+/// Simulates `msgs` and returns the gas limit to sign with.
+pub async fn estimate_gas(
+    client: &SigningClient,
+    msgs: &[EncodedMsg],
+    memo: &str,
+    multiplier: f64,
+) -> Result<u64, Error> {
+    let gas_used = client.simulate(msgs, memo).await?;
+    Ok(gas_limit(gas_used, multiplier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    const USCRT: FeeDenom = FeeDenom {
+        denom: "uscrt",
+        symbol: "SCRT",
+        decimals: 6,
+        gas_price_step: GasPriceStep {
+            low: 0.1,
+            average: 0.25,
+            high: 0.5,
+        },
+    };
+
+    #[wasm_bindgen_test]
+    fn amounts() {
+        assert_eq!(gas_limit(100_000, DEFAULT_GAS_MULTIPLIER), 130_000);
+        assert_eq!(gas_limit(100_001, 1.0), 100_001);
+        assert_eq!(fee_amount(100_000, 0.25), 25_000);
+        // fractional results round up
+        assert_eq!(fee_amount(100_001, 0.25), 25_001);
+        assert_eq!(fee_amount(3, 0.0125), 1);
+        assert_eq!(fee_amount(100_000, 0.0), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn options() {
+        let fee = FeeOption::new(FeeTier::High, &USCRT, 200_000);
+        assert_eq!(fee.amount.to_string(), "0.1 SCRT");
+        assert_eq!(fee.amount.denom, "uscrt");
+
+        let chain = ChainConfig::default();
+        let options = fee_options(&chain, 200_000);
+        assert_eq!(options.len(), chain.fee_denoms.len() * FeeTier::ALL.len());
+        for (choice, option) in &options {
+            assert_eq!(choice.fee(&chain, 200_000).as_ref(), Some(option));
+        }
+        assert_eq!(
+            FeeChoice::default().fee(&chain, 200_000),
+            Some(FeeOption::new(
+                FeeTier::Average,
+                &chain.fee_denoms[0],
+                200_000
+            ))
+        );
+        assert_eq!(
+            FeeChoice {
+                tier: FeeTier::Low,
+                denom: Some("uatom"),
+            }
+            .fee(&chain, 200_000),
+            None
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! The connected account's transaction history.
+//!
+//! There's no single query for "every tx involving an address", so two event queries are made
+//! (`message.sender` and `transfer.recipient`) and their results are merged. Each query keeps
+//! its own offset in a [`HistoryCursor`], and a page only holds txs above the lowest height both
+//! queries have fully covered, so pages stay in order and a tx found by both appears once. Each
+//! message is decoded into a [`HistoryMsg`] the UI can describe in a sentence.
+
+use crate::{
+    coin::{Amount, Coin, DenomInfo},
+    config::ChainConfig,
+    error::Error,
+    query_cache::{self, cached_query, CacheKey},
+    state::{TokenMap, WasmClient},
+};
+use leptos::prelude::*;
+use secretrs::{
+    bank::MsgSend,
+    compute::MsgExecuteContract,
+    proto::{
+        cosmos::{
+            base::{abci::v1beta1::TxResponse, query::v1beta1::PageRequest},
+            tx::v1beta1::{service_client::ServiceClient, GetTxsEventRequest, OrderBy, Tx},
+        },
+        traits::Message,
+    },
+    staking::MsgDelegate,
+    tx::Msg,
+    Any,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+use tonic_web_wasm_client::Client;
+use tracing::{debug, warn};
+
+/// Transactions fetched per event query, so a page has up to twice as many rows.
+pub const PAGE_SIZE: u64 = 20;
+/// Older pages are shown right away and refreshed in the background.
+const HISTORY_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum HistoryMsg {
+    Send {
+        from: String,
+        to: String,
+        amount: Vec<Coin>,
+    },
+    Delegate {
+        validator: String,
+        amount: Coin,
+    },
+    /// The message itself is encrypted, so only the contract and funds are known.
+    Execute {
+        contract: String,
+        sent_funds: Vec<Coin>,
+    },
+    Other {
+        type_url: String,
+    },
+}
+
+impl HistoryMsg {
+    fn decode(any: &Any, chain: &ChainConfig) -> Self {
+        let decoded = match any.type_url.as_str() {
+            "/cosmos.bank.v1beta1.MsgSend" => MsgSend::from_any(any).ok().map(|msg| Self::Send {
+                from: msg.from_address.to_string(),
+                to: msg.to_address.to_string(),
+                amount: coins(&msg.amount, chain),
+            }),
+            "/cosmos.staking.v1beta1.MsgDelegate" => {
+                MsgDelegate::from_any(any).ok().map(|msg| Self::Delegate {
+                    validator: msg.validator_address.to_string(),
+                    amount: coin(&msg.amount, chain),
+                })
+            }
+            "/secret.compute.v1beta1.MsgExecuteContract" => MsgExecuteContract::from_any(any)
+                .ok()
+                .map(|msg| Self::Execute {
+                    contract: msg.contract.to_string(),
+                    sent_funds: coins(&msg.sent_funds, chain),
+                }),
+            _ => None,
+        };
+
+        decoded.unwrap_or_else(|| Self::Other {
+            type_url: any.type_url.clone(),
+        })
+    }
+
+    /// What happened, from the point of view of `address`. Contracts in `tokens` go by their
+    /// token's name.
+    pub fn describe(&self, address: &str, tokens: &TokenMap) -> String {
+        match self {
+            Self::Send { from, to, amount } if to == address && from != address => {
+                format!("Received {} from {from}", join(amount))
+            }
+            Self::Send { to, amount, .. } => format!("Sent {} to {to}", join(amount)),
+            Self::Delegate { validator, amount } => format!("Delegated {amount} to {validator}"),
+            Self::Execute {
+                contract,
+                sent_funds,
+            } => {
+                let name = tokens
+                    .get(contract)
+                    .map(|token| token.metadata.name.as_str())
+                    .unwrap_or(contract.as_str());
+                match sent_funds.is_empty() {
+                    true => format!("Executed {name}"),
+                    false => format!("Executed {name} with {}", join(sent_funds)),
+                }
+            }
+            // "/cosmos.gov.v1beta1.MsgVote" reads as "MsgVote"
+            Self::Other { type_url } => type_url.rsplit('.').next().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Amounts in the chain's base denom get its symbol and decimals. Others are shown raw.
+fn coin(coin: &secretrs::Coin, chain: &ChainConfig) -> Coin {
+    let denom = coin.denom.to_string();
+    let info = match denom == chain.denom {
+        true => DenomInfo::from(chain),
+        false => DenomInfo {
+            symbol: denom.clone(),
+            denom,
+            decimals: 0,
+        },
+    };
+    Coin::new(&info, Amount::new(coin.amount, info.decimals))
+}
+
+fn coins(coins: &[secretrs::Coin], chain: &ChainConfig) -> Vec<Coin> {
+    coins.iter().map(|c| coin(c, chain)).collect()
+}
+
+fn join(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryRow {
+    pub tx_hash: String,
+    pub height: i64,
+    /// RFC 3339, as the node reports it.
+    pub timestamp: String,
+    /// 0 on success.
+    pub code: u32,
+    pub messages: Vec<HistoryMsg>,
+}
+
+impl HistoryRow {
+    fn new(response: &TxResponse, chain: &ChainConfig) -> Self {
+        let messages = response
+            .tx
+            .as_ref()
+            .and_then(|any| Tx::decode(any.value.as_slice()).ok())
+            .and_then(|tx| tx.body)
+            .map(|body| {
+                body.messages
+                    .iter()
+                    .map(|any| HistoryMsg::decode(any, chain))
+                    .collect()
+            })
+            .unwrap_or_else(|| {
+                warn!("could not decode {}", response.txhash);
+                vec![]
+            });
+
+        Self {
+            tx_hash: response.txhash.clone(),
+            height: response.height,
+            timestamp: response.timestamp.clone(),
+            code: response.code,
+            messages,
+        }
+    }
+}
+
+/// Where a page starts: how many txs of each query earlier pages showed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HistoryCursor {
+    sent: u64,
+    received: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HistoryPage {
+    /// Newest first.
+    pub rows: Vec<HistoryRow>,
+    /// Where the next page starts, if there is one.
+    pub next: Option<HistoryCursor>,
+}
+
+/// Txs matching an event, newest first, from some offset on.
+struct Batch {
+    txs: Vec<TxResponse>,
+    /// Whether the query has more results after `txs`.
+    more: bool,
+}
+
+impl Batch {
+    /// The lowest height up to which every matching tx is in `txs`, or `None` if all of them are.
+    fn covered_down_to(&self) -> Option<i64> {
+        match self.more {
+            true => self.txs.last().map(|tx| tx.height),
+            false => None,
+        }
+    }
+}
+
+/// Up to [`PAGE_SIZE`] txs matching `event`, newest first, skipping the first `offset`.
+async fn txs_by_event(client: Client, event: &str, offset: u64) -> Result<Batch, Error> {
+    let request = GetTxsEventRequest {
+        events: vec![event.to_string()],
+        pagination: Some(PageRequest {
+            offset,
+            limit: PAGE_SIZE,
+            count_total: true,
+            ..Default::default()
+        }),
+        order_by: OrderBy::Desc.into(),
+        ..Default::default()
+    };
+    let response = ServiceClient::new(client)
+        .get_txs_event(request)
+        .await
+        .map_err(|status| Error::Secret(status.message().to_string()))?
+        .into_inner();
+
+    let total = response.pagination.map(|p| p.total).unwrap_or_default();
+    let more = offset + (response.tx_responses.len() as u64) < total;
+    Ok(Batch {
+        txs: response.tx_responses,
+        more,
+    })
+}
+
+/// Merges the batches both queries returned from `cursor` into a page.
+///
+/// A query that has more results may be missing txs at or below the height of its last one, so
+/// only txs above that height are shown and the rest are left for the next page. That keeps the
+/// merged stream in order, and a tx found by both queries (sending to yourself) always lands on
+/// the same page, where it's kept once.
+fn merge(cursor: HistoryCursor, sent: Batch, received: Batch, chain: &ChainConfig) -> HistoryPage {
+    let covered = sent.covered_down_to().max(received.covered_down_to());
+    // if a whole batch shares one height, showing it is the only way forward
+    let stuck = covered.is_some_and(|covered| {
+        sent.txs
+            .iter()
+            .chain(&received.txs)
+            .all(|tx| tx.height <= covered)
+    });
+    let shown = |tx: &&TxResponse| match covered {
+        None => true,
+        Some(covered) => tx.height > covered || (stuck && tx.height == covered),
+    };
+
+    let sent_shown: Vec<&TxResponse> = sent.txs.iter().filter(shown).collect();
+    let received_shown: Vec<&TxResponse> = received.txs.iter().filter(shown).collect();
+    let next = HistoryCursor {
+        sent: cursor.sent + sent_shown.len() as u64,
+        received: cursor.received + received_shown.len() as u64,
+    };
+    let has_more = sent.more
+        || received.more
+        || sent_shown.len() < sent.txs.len()
+        || received_shown.len() < received.txs.len();
+
+    let mut seen = HashSet::new();
+    let mut rows: Vec<HistoryRow> = sent_shown
+        .into_iter()
+        .chain(received_shown)
+        .filter(|response| seen.insert(response.txhash.clone()))
+        .map(|response| HistoryRow::new(response, chain))
+        .collect();
+    rows.sort_by(|a, b| b.height.cmp(&a.height));
+
+    HistoryPage {
+        rows,
+        next: has_more.then_some(next),
+    }
+}
+
+fn page_key(chain_id: &str, address: &str, cursor: HistoryCursor) -> CacheKey {
+    let HistoryCursor { sent, received } = cursor;
+    CacheKey::account(chain_id, address, &format!("history:{sent}:{received}"))
+}
+
+/// Drops the cached first page of `address`'s history, which is where new txs show up.
+pub fn invalidate_latest(chain_id: &str, address: &str) {
+    query_cache::invalidate(&page_key(chain_id, address, HistoryCursor::default()));
+}
+
+/// The page of `address`'s history starting at `cursor`, cached per address and cursor.
+pub async fn history_page(
+    wasm_client: WasmClient,
+    chain: ChainConfig,
+    address: String,
+    cursor: HistoryCursor,
+    revalidated: Trigger,
+) -> Result<HistoryPage, Error> {
+    let key = page_key(chain.chain_id, &address, cursor);
+
+    cached_query(key, HISTORY_TTL, revalidated, move || async move {
+        debug!("querying the history of {address} from {cursor:?}");
+        let sent_event = format!("message.sender='{address}'");
+        let received_event = format!("transfer.recipient='{address}'");
+
+        let (sent, received) = futures::future::try_join(
+            wasm_client.query(|client| txs_by_event(client, &sent_event, cursor.sent)),
+            wasm_client.query(|client| txs_by_event(client, &received_event, cursor.received)),
+        )
+        .await?;
+
+        Ok(merge(cursor, sent, received, &chain))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    const ALICE: &str = "secret1ap26qrlp8mcq2pg6r47w43l0y8zkqm8a450s03";
+    const BOB: &str = "secret1fc3fzy78ttp0lwuujw7e52rhspxn8uj52zfyne";
+
+    fn response(txhash: &str, height: i64) -> TxResponse {
+        TxResponse {
+            txhash: txhash.to_string(),
+            height,
+            ..Default::default()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn decode_and_describe() {
+        let chain = ChainConfig::default();
+        let any = MsgSend {
+            from_address: ALICE.parse().unwrap(),
+            to_address: BOB.parse().unwrap(),
+            amount: vec![secretrs::Coin {
+                denom: "uscrt".parse().unwrap(),
+                amount: 1_500_000,
+            }],
+        }
+        .to_any()
+        .unwrap();
+        let msg = HistoryMsg::decode(&any, &chain);
+
+        let tokens = TokenMap::new(&chain);
+        assert_eq!(
+            msg.describe(ALICE, &tokens),
+            format!("Sent 1.5 SCRT to {BOB}")
+        );
+        assert_eq!(
+            msg.describe(BOB, &tokens),
+            format!("Received 1.5 SCRT from {ALICE}")
+        );
+
+        let other = HistoryMsg::decode(
+            &Any {
+                type_url: "/cosmos.gov.v1beta1.MsgVote".to_string(),
+                value: vec![],
+            },
+            &chain,
+        );
+        assert_eq!(other.describe(ALICE, &tokens), "MsgVote");
+    }
+
+    /// `size` txs of `txs` from `offset` on, as the node would page them.
+    fn batch(txs: &[TxResponse], offset: u64, size: usize) -> Batch {
+        let txs: Vec<_> = txs.iter().skip(offset as usize).cloned().collect();
+        Batch {
+            more: txs.len() > size,
+            txs: txs.into_iter().take(size).collect(),
+        }
+    }
+
+    fn hashes(page: &HistoryPage) -> Vec<&str> {
+        page.rows.iter().map(|row| row.tx_hash.as_str()).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn merged_pages() {
+        let chain = ChainConfig::default();
+        let page = merge(
+            HistoryCursor::default(),
+            batch(&[response("B", 20), response("A", 10)], 0, 2),
+            batch(&[response("C", 30), response("B", 20)], 0, 2),
+            &chain,
+        );
+        assert_eq!(hashes(&page), ["C", "B", "A"]);
+        assert_eq!(page.next, None);
+    }
+
+    #[wasm_bindgen_test]
+    fn paging_keeps_order_across_queries() {
+        let chain = ChainConfig::default();
+        // D is a send to yourself, so both queries find it
+        let sent = [
+            response("F", 60),
+            response("D", 40),
+            response("C", 30),
+            response("A", 10),
+        ];
+        let received = [response("E", 50), response("D", 40), response("B", 20)];
+
+        let mut pages = vec![];
+        let mut cursor = Some(HistoryCursor::default());
+        while let Some(from) = cursor {
+            let page = merge(
+                from,
+                batch(&sent, from.sent, 2),
+                batch(&received, from.received, 2),
+                &chain,
+            );
+            cursor = page.next;
+            pages.push(page);
+        }
+
+        let pages: Vec<_> = pages.iter().map(hashes).collect();
+        assert_eq!(pages, [vec!["F", "E"], vec!["D"], vec!["C", "B", "A"]]);
+    }
+
+    #[wasm_bindgen_test]
+    fn paging_gets_past_a_crowded_block() {
+        let chain = ChainConfig::default();
+        let sent = [response("C", 30), response("B", 30), response("A", 10)];
+
+        let first = merge(
+            HistoryCursor::default(),
+            batch(&sent, 0, 2),
+            batch(&[], 0, 2),
+            &chain,
+        );
+        assert_eq!(hashes(&first), ["C", "B"]);
+
+        let cursor = first.next.unwrap();
+        let second = merge(
+            cursor,
+            batch(&sent, cursor.sent, 2),
+            batch(&[], 0, 2),
+            &chain,
+        );
+        assert_eq!(hashes(&second), ["A"]);
+        assert_eq!(second.next, None);
+    }
+}
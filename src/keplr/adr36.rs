@@ -0,0 +1,78 @@
+//! Verification of ADR-36 (arbitrary message) signatures, without Keplr.
+//!
+//! See <https://github.com/cosmos/cosmos-sdk/blob/main/docs/architecture/adr-036-arbitrary-signature.md>
+
+use super::{Error, Key};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use rsecret::wallet::StdSignature;
+
+/// Builds the amino JSON sign doc that wallets sign for `data`.
+///
+/// The keys must be sorted and there can't be any whitespace, otherwise the bytes (and the
+/// signature) won't match.
+pub fn sign_doc_bytes(signer: &str, data: &[u8]) -> Vec<u8> {
+    let data = BASE64_STANDARD.encode(data);
+    // serializing a `&str` can't fail
+    let signer = serde_json::to_string(signer).unwrap();
+
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{data}","signer":{signer}}}}}],"sequence":"0"}}"#
+    )
+    .into_bytes()
+}
+
+/// Checks that `signature` is `key`'s signature over `data`.
+pub fn verify(key: &Key, data: &[u8], signature: &StdSignature) -> Result<(), Error> {
+    let signature = BASE64_STANDARD
+        .decode(&signature.signature)
+        .map_err(|error| Error::InvalidSignature(error.to_string()))?;
+
+    verify_bytes(&key.pub_key, &key.bech32_address, data, &signature)
+}
+
+/// Same as [`verify`], with the public key and signature as raw bytes.
+pub fn verify_bytes(
+    pub_key: &[u8],
+    signer: &str,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    let verifying_key = VerifyingKey::from_sec1_bytes(pub_key)
+        .map_err(|_| Error::InvalidSignature("malformed public key".to_string()))?;
+    let signature = Signature::from_slice(signature)
+        .map_err(|_| Error::InvalidSignature("malformed signature".to_string()))?;
+
+    // hashes the sign doc with sha256, same as the Cosmos SDK
+    verifying_key
+        .verify(&sign_doc_bytes(signer, data), &signature)
+        .map_err(|_| Error::InvalidSignature("verification failed".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    // Produced outside this crate: the sign doc was built with Python's
+    // `json.dumps(sort_keys=True, separators=(",", ":"))` and signed with OpenSSL's secp256k1
+    // (low-S). The key is sha256("secret-leptos adr36 test key").
+    const SIGNER: &str = "secret1trnu6cdscj4v67xnw6437vcrrhtef3v4mxa0g9";
+    const PUB_KEY: &str = "A9YWFqY6wg6+Ank3Hh6onnXENmUseojmHR6nK+ahn4NS";
+    const OTHER_SIGNER: &str = "secret10xcqpzrky6eff2g52qdye53xkk9jxkvrr9w4al";
+    const DATA: &[u8] = b"Sign in to secret-leptos";
+    const SIGNATURE: &str =
+        "NNWbptjXyb7cQ9Vb1xVb2wwvpQIs09+AIgUXkoFlyJMQQz/0xwa7oPWMVh5DKsIJEMl3zVHJLVxz1PDX7M4ccQ==";
+    const SIGN_DOC: &str = r#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"sign/MsgSignData","value":{"data":"U2lnbiBpbiB0byBzZWNyZXQtbGVwdG9z","signer":"secret1trnu6cdscj4v67xnw6437vcrrhtef3v4mxa0g9"}}],"sequence":"0"}"#;
+
+    #[wasm_bindgen_test]
+    fn known_answer() {
+        let pub_key = BASE64_STANDARD.decode(PUB_KEY).unwrap();
+        let signature = BASE64_STANDARD.decode(SIGNATURE).unwrap();
+
+        assert_eq!(sign_doc_bytes(SIGNER, DATA), SIGN_DOC.as_bytes());
+        assert_eq!(verify_bytes(&pub_key, SIGNER, DATA, &signature), Ok(()));
+        assert!(verify_bytes(&pub_key, SIGNER, b"Sign in to something else", &signature).is_err());
+        assert!(verify_bytes(&pub_key, OTHER_SIGNER, DATA, &signature).is_err());
+    }
+}
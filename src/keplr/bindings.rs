@@ -0,0 +1,126 @@
+//! Bindings for the parts of the Keplr API that `keplr_sys` doesn't cover (yet).
+
+use web_sys::{
+    js_sys,
+    wasm_bindgen::{self, prelude::*, JsCast},
+};
+
+#[wasm_bindgen]
+extern "C" {
+    /// Any of the offline signers returned by `getOfflineSigner*`.
+    pub type OfflineSigner;
+
+    #[wasm_bindgen(method, getter, js_name = chainId)]
+    pub fn chain_id(this: &OfflineSigner) -> String;
+
+    /// What `getEnigmaUtils` returns. Encrypts with a seed that the wallet derives from the
+    /// user's key, so results can be decrypted again in later sessions.
+    pub type EnigmaUtils;
+
+    #[wasm_bindgen(method, catch)]
+    pub async fn encrypt(
+        this: &EnigmaUtils,
+        contract_code_hash: &str,
+        msg: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    pub async fn decrypt(
+        this: &EnigmaUtils,
+        ciphertext: &[u8],
+        nonce: &[u8],
+    ) -> Result<JsValue, JsValue>;
+
+    /// The API object that Keplr (and Keplr-compatible extensions) inject into `window`.
+    #[derive(Clone)]
+    pub type Provider;
+
+    #[wasm_bindgen(method, catch)]
+    pub async fn enable(this: &Provider, chain_ids: Vec<String>) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, js_name = getKey, catch)]
+    pub async fn get_key(this: &Provider, chain_id: &str) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, js_name = getOfflineSigner)]
+    pub fn get_offline_signer(this: &Provider, chain_id: &str) -> keplr_sys::KeplrOfflineSigner;
+
+    #[wasm_bindgen(method, js_name = getOfflineSignerOnlyAmino)]
+    pub fn get_offline_signer_only_amino(
+        this: &Provider,
+        chain_id: &str,
+    ) -> keplr_sys::KeplrOfflineSignerOnlyAmino;
+
+    #[wasm_bindgen(method, js_name = getEnigmaUtils)]
+    pub fn get_enigma_utils(this: &Provider, chain_id: &str) -> keplr_sys::EnigmaUtils;
+
+    #[wasm_bindgen(method, js_name = experimentalSuggestChain, catch)]
+    pub async fn suggest_chain(this: &Provider, chain_info: JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, js_name = suggestToken, catch)]
+    pub async fn suggest_token(
+        this: &Provider,
+        chain_id: &str,
+        contract_address: &str,
+        viewing_key: Option<String>,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, js_name = getSecret20ViewingKey, catch)]
+    pub async fn get_secret_20_viewing_key(
+        this: &Provider,
+        chain_id: &str,
+        contract_address: &str,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method)]
+    pub fn disable(this: &Provider, chain_id: &str);
+
+    #[wasm_bindgen(method, js_name = signDirect, catch)]
+    pub async fn sign_direct(
+        this: &Provider,
+        chain_id: &str,
+        signer: &str,
+        sign_doc: JsValue,
+        sign_options: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, js_name = signAmino, catch)]
+    pub async fn sign_amino(
+        this: &Provider,
+        chain_id: &str,
+        signer: &str,
+        sign_doc: JsValue,
+        sign_options: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, js_name = signArbitrary, catch)]
+    pub async fn sign_arbitrary(
+        this: &Provider,
+        chain_id: &str,
+        signer: &str,
+        data: JsValue,
+    ) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, js_name = verifyArbitrary, catch)]
+    pub async fn verify_arbitrary(
+        this: &Provider,
+        chain_id: &str,
+        signer: &str,
+        data: JsValue,
+        signature: JsValue,
+    ) -> Result<JsValue, JsValue>;
+}
+
+/// Looks up the provider an extension injected as `window[global]`.
+pub fn injected(global: &str) -> Option<Provider> {
+    web_sys::window()
+        .and_then(|window| js_sys::Reflect::get(&window, &JsValue::from_str(global)).ok())
+        .filter(|provider| !provider.is_undefined() && !provider.is_null())
+        .map(JsCast::unchecked_into)
+}
+
+/// `window.keplr`, looked up on every call since it can be injected (or replaced) late.
+///
+/// If it's missing, calls on the returned provider fail with a JS `TypeError`.
+pub fn keplr() -> Provider {
+    injected("keplr").unwrap_or_else(|| JsValue::UNDEFINED.unchecked_into())
+}
@@ -0,0 +1,53 @@
+use super::{bindings, Error};
+use crate::enigma::{Enigma, NONCE_LEN};
+use async_trait::async_trait;
+use serde::Serialize;
+use web_sys::{js_sys::Uint8Array, wasm_bindgen::JsCast};
+
+/// [`Enigma`] backed by the wallet's `EnigmaUtils`, so messages are encrypted with the seed the
+/// wallet keeps for the user's account.
+#[derive(Clone)]
+pub struct KeplrEnigmaUtils {
+    inner: bindings::EnigmaUtils,
+}
+
+impl From<keplr_sys::EnigmaUtils> for KeplrEnigmaUtils {
+    fn from(value: keplr_sys::EnigmaUtils) -> Self {
+        Self {
+            inner: value.unchecked_into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Enigma for KeplrEnigmaUtils {
+    async fn encrypt(
+        &self,
+        code_hash: &str,
+        msg: &serde_json::Value,
+    ) -> Result<Vec<u8>, crate::Error> {
+        // the wallet serializes the message itself, so it needs plain JS objects, not Maps
+        let msg = msg
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(Error::from)?;
+        let encrypted = self
+            .inner
+            .encrypt(code_hash, msg)
+            .await
+            .map_err(Error::from)?;
+        Ok(Uint8Array::new(&encrypted).to_vec())
+    }
+
+    async fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8; NONCE_LEN],
+    ) -> Result<Vec<u8>, crate::Error> {
+        let decrypted = self
+            .inner
+            .decrypt(ciphertext, nonce)
+            .await
+            .map_err(Error::from)?;
+        Ok(Uint8Array::new(&decrypted).to_vec())
+    }
+}
@@ -10,6 +10,21 @@ pub enum Error {
 
     #[error("Keplr is unavailable!")]
     KeplrUnavailable,
+
+    #[error("Request rejected by the user")]
+    RequestRejected,
+
+    #[error("Signer does not match the selected account: {0}")]
+    SignerMismatch(String),
+
+    #[error("This signer doesn't support the requested sign mode")]
+    UnsupportedSignMode,
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Invalid chain info: {0}")]
+    InvalidChainInfo(String),
 }
 
 impl From<wasm_bindgen::JsValue> for Error {
@@ -18,7 +33,13 @@ impl From<wasm_bindgen::JsValue> for Error {
             .message()
             .as_string()
             .unwrap_or("unknown JS error".to_string());
-        Error::JavaScript(message)
+
+        // Keplr doesn't expose error codes to the page, so match on the message instead
+        match message.as_str() {
+            "Request rejected" => Error::RequestRejected,
+            msg if msg.contains("Signer mismatched") => Error::SignerMismatch(message),
+            _ => Error::JavaScript(message),
+        }
     }
 }
 impl From<serde_wasm_bindgen::Error> for Error {
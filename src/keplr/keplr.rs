@@ -1,4 +1,4 @@
-use super::Error;
+use super::{bindings, suggest_chain_types::ChainInfo, Error};
 use async_trait::async_trait;
 use base64::prelude::{Engine as _, BASE64_STANDARD};
 use keplr_sys::*;
@@ -8,8 +8,8 @@ use std::rc::Rc;
 use tracing::debug;
 use web_sys::{
     console,
-    js_sys::{self, JsString},
-    wasm_bindgen,
+    js_sys::{self, JsString, Uint8Array},
+    wasm_bindgen::{self, JsCast, JsValue},
 };
 
 use rsecret::wallet::*;
@@ -49,6 +49,112 @@ impl std::fmt::Debug for Key {
     }
 }
 
+/// Options that change how Keplr presents a signing request to the user.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeplrSignOptions {
+    /// Use the fee from the sign doc instead of letting the user choose one.
+    pub prefer_no_set_fee: bool,
+    /// Use the memo from the sign doc instead of letting the user edit it.
+    pub prefer_no_set_memo: bool,
+    /// Skip Keplr's check that the account can afford the fee.
+    pub disable_balance_check: bool,
+}
+
+/// Either of the Keplr signers, picked by [`Keplr::get_offline_signer_auto`].
+#[derive(Clone)]
+pub enum KeplrSigner {
+    Direct(KeplrOfflineSigner),
+    OnlyAmino(KeplrOfflineSignerOnlyAmino),
+}
+
+impl KeplrSigner {
+    pub fn with_sign_options(self, sign_options: KeplrSignOptions) -> Self {
+        match self {
+            Self::Direct(signer) => Self::Direct(signer.with_sign_options(sign_options)),
+            Self::OnlyAmino(signer) => Self::OnlyAmino(signer.with_sign_options(sign_options)),
+        }
+    }
+
+    pub fn chain_id(&self) -> String {
+        match self {
+            Self::Direct(signer) => signer.chain_id(),
+            Self::OnlyAmino(signer) => signer.chain_id(),
+        }
+    }
+}
+
+impl From<KeplrOfflineSigner> for KeplrSigner {
+    fn from(value: KeplrOfflineSigner) -> Self {
+        Self::Direct(value)
+    }
+}
+
+impl From<KeplrOfflineSignerOnlyAmino> for KeplrSigner {
+    fn from(value: KeplrOfflineSignerOnlyAmino) -> Self {
+        Self::OnlyAmino(value)
+    }
+}
+
+#[async_trait]
+impl Signer for KeplrSigner {
+    type Error = super::Error;
+
+    async fn get_accounts(&self) -> Result<Vec<AccountData>, Self::Error> {
+        match self {
+            Self::Direct(signer) => signer.get_accounts().await,
+            Self::OnlyAmino(signer) => signer.get_accounts().await,
+        }
+    }
+
+    async fn get_sign_mode(&self) -> Result<SignMode, Self::Error> {
+        match self {
+            Self::Direct(signer) => signer.get_sign_mode().await,
+            Self::OnlyAmino(signer) => signer.get_sign_mode().await,
+        }
+    }
+
+    async fn sign_amino(
+        &self,
+        signer_address: &str,
+        sign_doc: StdSignDoc,
+    ) -> Result<AminoSignResponse, Self::Error> {
+        match self {
+            Self::Direct(signer) => signer.sign_amino(signer_address, sign_doc).await,
+            Self::OnlyAmino(signer) => signer.sign_amino(signer_address, sign_doc).await,
+        }
+    }
+
+    async fn sign_permit(
+        &self,
+        signer_address: &str,
+        sign_doc: StdSignDoc,
+    ) -> Result<AminoSignResponse, Self::Error> {
+        match self {
+            Self::Direct(signer) => signer.sign_permit(signer_address, sign_doc).await,
+            Self::OnlyAmino(signer) => signer.sign_permit(signer_address, sign_doc).await,
+        }
+    }
+
+    async fn sign_direct(
+        &self,
+        signer_address: &str,
+        sign_doc: SignDocVariant,
+    ) -> Result<DirectSignResponse, Self::Error> {
+        match self {
+            Self::Direct(signer) => signer.sign_direct(signer_address, sign_doc).await,
+            Self::OnlyAmino(signer) => signer.sign_direct(signer_address, sign_doc).await,
+        }
+    }
+}
+
+// Permits are never broadcast, so the zero fee and empty memo must be signed exactly as given.
+const PERMIT_SIGN_OPTIONS: KeplrSignOptions = KeplrSignOptions {
+    prefer_no_set_fee: true,
+    prefer_no_set_memo: true,
+    disable_balance_check: true,
+};
+
 pub struct Keplr {}
 
 impl Keplr {
@@ -99,14 +205,12 @@ impl Keplr {
         get_offline_signer_only_amino(chain_id).into()
     }
 
-    // TODO: this doesn't help... idk what to do here
-    pub async fn get_offline_signer_auto(
-        chain_id: &str,
-    ) -> Result<Box<dyn Signer<Error = Error>>, Error> {
+    pub async fn get_offline_signer_auto(chain_id: &str) -> Result<KeplrSigner, Error> {
         let key = Self::get_key(chain_id).await?;
-        let signer: Box<dyn Signer<Error = Error>> = match key.is_nano_ledger {
-            true => Box::new(Self::get_offline_signer_only_amino(chain_id)),
-            false => Box::new(Self::get_offline_signer(chain_id)),
+        // Hardware wallets can only sign amino (JSON) messages
+        let signer = match key.is_nano_ledger || key.is_keystone {
+            true => Self::get_offline_signer_only_amino(chain_id).into(),
+            false => Self::get_offline_signer(chain_id).into(),
         };
         Ok(signer)
     }
@@ -115,6 +219,16 @@ impl Keplr {
         get_enigma_utils(chain_id)
     }
 
+    /// Asks the user to add `chain_info` to Keplr. Does nothing if Keplr already knows the chain.
+    pub async fn suggest_chain(chain_info: ChainInfo) -> Result<(), Error> {
+        chain_info.validate()?;
+        let chain_info = serde_wasm_bindgen::to_value(&chain_info)?;
+        suggest_chain(chain_info)
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
     pub async fn suggest_token(
         chain_id: &str,
         contract_address: &str,
@@ -135,6 +249,25 @@ impl Keplr {
             .map_err(Into::into)
     }
 
+    /// Signs `data` as an ADR-36 message. Nothing is broadcast, so this works for sign-in and
+    /// proof-of-ownership flows. The result can be checked with [`super::adr36::verify`].
+    pub async fn sign_arbitrary(
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+    ) -> Result<StdSignature, Error> {
+        sign_arbitrary_with(&bindings::keplr(), chain_id, signer, data).await
+    }
+
+    pub async fn verify_arbitrary(
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+        signature: &StdSignature,
+    ) -> Result<bool, Error> {
+        verify_arbitrary_with(&bindings::keplr(), chain_id, signer, data, signature).await
+    }
+
     pub fn disable(chain_id: &str) {
         disable(chain_id)
     }
@@ -144,30 +277,74 @@ impl Keplr {
     }
 }
 
+/// [`Keplr::sign_arbitrary`] with any Keplr-compatible `provider`.
+pub(crate) async fn sign_arbitrary_with(
+    provider: &bindings::Provider,
+    chain_id: &str,
+    signer: &str,
+    data: &[u8],
+) -> Result<StdSignature, Error> {
+    let signature = provider
+        .sign_arbitrary(chain_id, signer, Uint8Array::from(data).into())
+        .await?;
+    Ok(serde_wasm_bindgen::from_value(signature)?)
+}
+
+/// [`Keplr::verify_arbitrary`] with any Keplr-compatible `provider`.
+pub(crate) async fn verify_arbitrary_with(
+    provider: &bindings::Provider,
+    chain_id: &str,
+    signer: &str,
+    data: &[u8],
+    signature: &StdSignature,
+) -> Result<bool, Error> {
+    let signature = serde_wasm_bindgen::to_value(signature)?;
+    provider
+        .verify_arbitrary(chain_id, signer, Uint8Array::from(data).into(), signature)
+        .await
+        .map(|verified| verified.is_truthy())
+        .map_err(Into::into)
+}
+
 #[derive(Clone)]
 pub struct KeplrOfflineSigner {
     inner: SendWrapper<Rc<keplr_sys::KeplrOfflineSigner>>,
+    // the extension that created the signer, which is also the one that has to sign
+    provider: SendWrapper<bindings::Provider>,
+    sign_options: Option<KeplrSignOptions>,
 }
 
 impl From<keplr_sys::KeplrOfflineSigner> for KeplrOfflineSigner {
     fn from(value: keplr_sys::KeplrOfflineSigner) -> Self {
+        Self::new(value, bindings::keplr())
+    }
+}
+
+impl KeplrOfflineSigner {
+    pub(crate) fn new(inner: keplr_sys::KeplrOfflineSigner, provider: bindings::Provider) -> Self {
         Self {
-            inner: SendWrapper::new(Rc::new(value)),
+            inner: SendWrapper::new(Rc::new(inner)),
+            provider: SendWrapper::new(provider),
+            sign_options: None,
         }
     }
+
+    pub fn with_sign_options(mut self, sign_options: KeplrSignOptions) -> Self {
+        self.sign_options = Some(sign_options);
+        self
+    }
+
+    pub fn chain_id(&self) -> String {
+        self.inner
+            .unchecked_ref::<bindings::OfflineSigner>()
+            .chain_id()
+    }
 }
 
 #[async_trait]
 impl Signer for KeplrOfflineSigner {
     type Error = super::Error;
 
-    // pub fn chain_id(&self) -> String {
-    //     self.inner
-    //         .chain_id()
-    //         .as_string()
-    //         .expect("chain_id field is missing!")
-    // }
-
     async fn get_accounts(&self) -> Result<Vec<AccountData>, Self::Error> {
         SendWrapper::new(async move {
             self.inner
@@ -194,7 +371,16 @@ impl Signer for KeplrOfflineSigner {
         signer_address: &str,
         sign_doc: StdSignDoc,
     ) -> Result<AminoSignResponse, Self::Error> {
-        todo!()
+        ensure_signer(&self.get_accounts().await?, signer_address)?;
+
+        sign_amino_with_keplr(
+            &self.provider,
+            &self.chain_id(),
+            signer_address,
+            &sign_doc,
+            self.sign_options.as_ref(),
+        )
+        .await
     }
 
     async fn sign_permit(
@@ -202,7 +388,16 @@ impl Signer for KeplrOfflineSigner {
         signer_address: &str,
         sign_doc: StdSignDoc,
     ) -> Result<AminoSignResponse, Self::Error> {
-        todo!()
+        ensure_signer(&self.get_accounts().await?, signer_address)?;
+
+        sign_amino_with_keplr(
+            &self.provider,
+            &self.chain_id(),
+            signer_address,
+            &sign_doc,
+            Some(&PERMIT_SIGN_OPTIONS),
+        )
+        .await
     }
 
     async fn sign_direct(
@@ -210,34 +405,171 @@ impl Signer for KeplrOfflineSigner {
         signer_address: &str,
         sign_doc: SignDocVariant,
     ) -> Result<DirectSignResponse, Self::Error> {
-        todo!()
+        ensure_signer(&self.get_accounts().await?, signer_address)?;
+
+        let chain_id = self.chain_id();
+        let sign_doc = sign_doc_to_js(sign_doc);
+        let sign_options = serde_wasm_bindgen::to_value(&self.sign_options)?;
+
+        SendWrapper::new(async move {
+            let response = self
+                .provider
+                .sign_direct(&chain_id, signer_address, sign_doc, sign_options)
+                .await?;
+
+            let signed = js_sys::Reflect::get(&response, &JsValue::from_str("signed"))?;
+            let signature = js_sys::Reflect::get(&response, &JsValue::from_str("signature"))?;
+
+            Ok(DirectSignResponse {
+                signed: SignDocVariant::SignDoc(sign_doc_from_js(&signed)?),
+                signature: serde_wasm_bindgen::from_value(signature)?,
+            })
+        })
+        .await
+    }
+}
+
+/// Fails early if `signer_address` isn't one of the signer's accounts.
+fn ensure_signer(accounts: &[AccountData], signer_address: &str) -> Result<(), Error> {
+    match accounts
+        .iter()
+        .any(|account| account.address == signer_address)
+    {
+        true => Ok(()),
+        false => Err(Error::SignerMismatch(signer_address.to_string())),
     }
 }
 
+async fn sign_amino_with_keplr(
+    provider: &bindings::Provider,
+    chain_id: &str,
+    signer_address: &str,
+    sign_doc: &StdSignDoc,
+    sign_options: Option<&KeplrSignOptions>,
+) -> Result<AminoSignResponse, Error> {
+    // The amino JSON has to stay plain objects (no `Map`s), or Keplr can't serialize it for signing
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    let sign_doc = sign_doc.serialize(&serializer)?;
+    let sign_options = sign_options.serialize(&serializer)?;
+
+    SendWrapper::new(async move {
+        let response = provider
+            .sign_amino(chain_id, signer_address, sign_doc, sign_options)
+            .await?;
+        Ok(serde_wasm_bindgen::from_value(response)?)
+    })
+    .await
+}
+
+/// Converts a sign doc into the shape Keplr's `signDirect` expects.
+fn sign_doc_to_js(sign_doc: SignDocVariant) -> JsValue {
+    let (body_bytes, auth_info_bytes, chain_id, account_number) = match sign_doc {
+        SignDocVariant::SignDoc(doc) => (
+            doc.body_bytes,
+            doc.auth_info_bytes,
+            doc.chain_id,
+            doc.account_number.to_string(),
+        ),
+        SignDocVariant::SignDocCamelCase(doc) => (
+            doc.body_bytes,
+            doc.auth_info_bytes,
+            doc.chain_id,
+            doc.account_number,
+        ),
+    };
+
+    let object = js_sys::Object::new();
+
+    let _ = js_sys::Reflect::set(
+        &object,
+        &JsValue::from_str("bodyBytes"),
+        &Uint8Array::from(body_bytes.as_slice()),
+    );
+    let _ = js_sys::Reflect::set(
+        &object,
+        &JsValue::from_str("authInfoBytes"),
+        &Uint8Array::from(auth_info_bytes.as_slice()),
+    );
+    let _ = js_sys::Reflect::set(
+        &object,
+        &JsValue::from_str("chainId"),
+        &JsValue::from_str(&chain_id),
+    );
+    // Keplr calls `toString()` on this (it's normally a `Long`), so a string works too
+    let _ = js_sys::Reflect::set(
+        &object,
+        &JsValue::from_str("accountNumber"),
+        &JsValue::from_str(&account_number),
+    );
+
+    object.into()
+}
+
+/// Converts the `signed` field of Keplr's `DirectSignResponse` back into a [`SignDoc`].
+fn sign_doc_from_js(value: &JsValue) -> Result<SignDoc, Error> {
+    let get = |key: &str| js_sys::Reflect::get(value, &JsValue::from_str(key));
+
+    let body_bytes = Uint8Array::new(&get("bodyBytes")?).to_vec();
+    let auth_info_bytes = Uint8Array::new(&get("authInfoBytes")?).to_vec();
+    let chain_id = get("chainId")?
+        .as_string()
+        .ok_or(Error::Serialization("chainId is not a string".to_string()))?;
+    let account_number = js_sys::Object::unchecked_from_js(get("accountNumber")?)
+        .to_string()
+        .as_string()
+        .and_then(|number| number.parse::<u64>().ok())
+        .ok_or(Error::Serialization("invalid accountNumber".to_string()))?;
+
+    Ok(SignDoc {
+        body_bytes,
+        auth_info_bytes,
+        chain_id,
+        account_number,
+    })
+}
+
 #[derive(Clone)]
 pub struct KeplrOfflineSignerOnlyAmino {
     inner: SendWrapper<Rc<keplr_sys::KeplrOfflineSignerOnlyAmino>>,
+    // the extension that created the signer, which is also the one that has to sign
+    provider: SendWrapper<bindings::Provider>,
+    sign_options: Option<KeplrSignOptions>,
 }
 
 impl From<keplr_sys::KeplrOfflineSignerOnlyAmino> for KeplrOfflineSignerOnlyAmino {
     fn from(value: keplr_sys::KeplrOfflineSignerOnlyAmino) -> Self {
+        Self::new(value, bindings::keplr())
+    }
+}
+
+impl KeplrOfflineSignerOnlyAmino {
+    pub(crate) fn new(
+        inner: keplr_sys::KeplrOfflineSignerOnlyAmino,
+        provider: bindings::Provider,
+    ) -> Self {
         Self {
-            inner: SendWrapper::new(Rc::new(value)),
+            inner: SendWrapper::new(Rc::new(inner)),
+            provider: SendWrapper::new(provider),
+            sign_options: None,
         }
     }
+
+    pub fn with_sign_options(mut self, sign_options: KeplrSignOptions) -> Self {
+        self.sign_options = Some(sign_options);
+        self
+    }
+
+    pub fn chain_id(&self) -> String {
+        self.inner
+            .unchecked_ref::<bindings::OfflineSigner>()
+            .chain_id()
+    }
 }
 
 #[async_trait]
 impl Signer for KeplrOfflineSignerOnlyAmino {
     type Error = super::Error;
 
-    // pub fn chain_id(&self) -> String {
-    //     self.inner
-    //         .chain_id()
-    //         .as_string()
-    //         .expect("chain_id field is missing!")
-    // }
-
     async fn get_accounts(&self) -> Result<Vec<AccountData>, Self::Error> {
         SendWrapper::new(async move {
             self.inner
@@ -264,7 +596,16 @@ impl Signer for KeplrOfflineSignerOnlyAmino {
         signer_address: &str,
         sign_doc: StdSignDoc,
     ) -> Result<AminoSignResponse, Self::Error> {
-        todo!()
+        ensure_signer(&self.get_accounts().await?, signer_address)?;
+
+        sign_amino_with_keplr(
+            &self.provider,
+            &self.chain_id(),
+            signer_address,
+            &sign_doc,
+            self.sign_options.as_ref(),
+        )
+        .await
     }
 
     async fn sign_permit(
@@ -272,76 +613,23 @@ impl Signer for KeplrOfflineSignerOnlyAmino {
         signer_address: &str,
         sign_doc: StdSignDoc,
     ) -> Result<AminoSignResponse, Self::Error> {
-        todo!()
+        ensure_signer(&self.get_accounts().await?, signer_address)?;
+
+        sign_amino_with_keplr(
+            &self.provider,
+            &self.chain_id(),
+            signer_address,
+            &sign_doc,
+            Some(&PERMIT_SIGN_OPTIONS),
+        )
+        .await
     }
 
     async fn sign_direct(
         &self,
-        signer_address: &str,
-        sign_doc: SignDocVariant,
+        _signer_address: &str,
+        _sign_doc: SignDocVariant,
     ) -> Result<DirectSignResponse, Self::Error> {
-        unimplemented!()
-    }
-}
-
-pub mod suggest_chain_types {
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct SuggestingChainInfo {
-        pub chain_id: String,
-        pub chain_name: String,
-        pub rpc: String,
-        pub rest: String,
-        pub bip44: Bip44,
-        pub bech32_config: Bech32Config,
-        pub currencies: Vec<Currency>,
-        pub fee_currencies: Vec<FeeCurrency>,
-        pub stake_currency: Currency,
-    }
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Bip44 {
-        pub coin_type: u32,
-    }
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Bech32Config {
-        pub bech32_prefix_acc_addr: String,
-        pub bech32_prefix_acc_pub: String,
-        pub bech32_prefix_val_addr: String,
-        pub bech32_prefix_val_pub: String,
-        pub bech32_prefix_cons_addr: String,
-        pub bech32_prefix_cons_pub: String,
-    }
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Currency {
-        pub coin_denom: String,
-        pub coin_minimal_denom: String,
-        pub coin_decimals: u8,
-        pub coin_gecko_id: String,
-    }
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct FeeCurrency {
-        pub coin_denom: String,
-        pub coin_minimal_denom: String,
-        pub coin_decimals: u8,
-        pub coin_gecko_id: String,
-        pub gas_price_step: GasPriceStep,
-    }
-
-    #[derive(Serialize, Deserialize, Debug)]
-    #[serde(rename_all = "camelCase")]
-    pub struct GasPriceStep {
-        pub low: f64,
-        pub average: f64,
-        pub high: f64,
+        Err(Error::UnsupportedSignMode)
     }
 }
@@ -0,0 +1,402 @@
+//! A fake `window.keplr` for headless tests.
+//!
+//! It behaves like an already unlocked Keplr with a single deterministic account, unless told to
+//! be locked or to reject every request.
+
+use super::adr36;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use k256::ecdsa::{signature::Signer as _, Signature, SigningKey};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    future::Future,
+    rc::Rc,
+};
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{
+    js_sys::{self, Array, Object, Promise, Reflect, Uint8Array},
+    wasm_bindgen::{closure::Closure, JsValue},
+};
+
+type Args = [JsValue; 4];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MockOptions {
+    /// Every request that needs the user's approval fails with "Request rejected".
+    pub reject: bool,
+    /// Every request fails until the mock is unlocked.
+    pub locked: bool,
+}
+
+#[derive(Default)]
+struct State {
+    options: MockOptions,
+    enabled: HashSet<String>,
+    viewing_keys: HashMap<String, String>,
+}
+
+#[derive(Clone)]
+pub struct MockKeplr {
+    state: Rc<RefCell<State>>,
+}
+
+impl MockKeplr {
+    pub const ADDRESS: &'static str = "secret10xcqpzrky6eff2g52qdye53xkk9jxkvrr9w4al";
+    pub const NAME: &'static str = "mock";
+
+    /// Installs a fresh mock as `window.keplr`, replacing any previous one.
+    pub fn install() -> Self {
+        Self::install_with(MockOptions::default())
+    }
+
+    pub fn install_with(options: MockOptions) -> Self {
+        let mock = Self {
+            state: Rc::new(RefCell::new(State {
+                options,
+                ..Default::default()
+            })),
+        };
+
+        let window = web_sys::window().expect("tests must run in a browser");
+        Reflect::set(&window, &JsValue::from_str("keplr"), &mock.to_js())
+            .expect("failed to install the mock");
+
+        mock
+    }
+
+    pub fn uninstall() {
+        let window = web_sys::window().expect("tests must run in a browser");
+        let _ = Reflect::delete_property(&window, &JsValue::from_str("keplr"));
+    }
+
+    pub fn set_options(&self, options: MockOptions) {
+        self.state.borrow_mut().options = options;
+    }
+
+    pub fn viewing_key(&self, contract_address: &str) -> Option<String> {
+        self.state
+            .borrow()
+            .viewing_keys
+            .get(contract_address)
+            .cloned()
+    }
+
+    pub fn signing_key() -> SigningKey {
+        SigningKey::from_slice(&[1; 32]).expect("valid secp256k1 key")
+    }
+
+    /// Compressed secp256k1 public key of the mock account.
+    pub fn pub_key() -> Vec<u8> {
+        Self::signing_key()
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    fn address_bytes() -> Vec<u8> {
+        // ripemd160(sha256(pub_key)), precomputed so the mock doesn't need more dependencies
+        BASE64_STANDARD
+            .decode("ebAAiHYmspSpFFAaTNImtYsjWYM=")
+            .unwrap()
+    }
+
+    fn to_js(&self) -> Object {
+        let keplr = Object::new();
+
+        let mock = self.clone();
+        async_method(&keplr, "enable", move |[chain_ids, ..]| {
+            let mock = mock.clone();
+            async move {
+                mock.approve()?;
+                let chain_ids = match chain_ids.as_string() {
+                    Some(chain_id) => vec![chain_id],
+                    None => Array::from(&chain_ids)
+                        .iter()
+                        .filter_map(|chain_id| chain_id.as_string())
+                        .collect(),
+                };
+                mock.state.borrow_mut().enabled.extend(chain_ids);
+                Ok(JsValue::UNDEFINED)
+            }
+        });
+
+        let mock = self.clone();
+        async_method(&keplr, "getKey", move |[chain_id, ..]| {
+            let mock = mock.clone();
+            async move {
+                mock.ensure_enabled(&chain_id)?;
+                Ok(key_js())
+            }
+        });
+
+        for name in ["getOfflineSigner", "getOfflineSignerOnlyAmino"] {
+            let mock = self.clone();
+            method(&keplr, name, move |[chain_id, ..]| {
+                mock.offline_signer(chain_id).into()
+            });
+        }
+
+        let mock = self.clone();
+        async_method(&keplr, "signDirect", move |[_, signer, sign_doc, _]| {
+            let mock = mock.clone();
+            async move {
+                mock.approve()?;
+                ensure_signer(&signer)?;
+
+                let mut message =
+                    Uint8Array::new(&Reflect::get(&sign_doc, &"bodyBytes".into())?).to_vec();
+                message.extend(
+                    Uint8Array::new(&Reflect::get(&sign_doc, &"authInfoBytes".into())?).to_vec(),
+                );
+
+                sign_response(sign_doc, &message)
+            }
+        });
+
+        let mock = self.clone();
+        async_method(&keplr, "signAmino", move |[_, signer, sign_doc, _]| {
+            let mock = mock.clone();
+            async move {
+                mock.approve()?;
+                ensure_signer(&signer)?;
+
+                let message = String::from(js_sys::JSON::stringify(&sign_doc)?);
+                sign_response(sign_doc, message.as_bytes())
+            }
+        });
+
+        let mock = self.clone();
+        async_method(&keplr, "signArbitrary", move |[_, signer, data, _]| {
+            let mock = mock.clone();
+            async move {
+                mock.approve()?;
+                ensure_signer(&signer)?;
+
+                let sign_doc = adr36::sign_doc_bytes(MockKeplr::ADDRESS, &data_bytes(&data));
+                Ok(std_signature(&sign_doc))
+            }
+        });
+
+        async_method(
+            &keplr,
+            "verifyArbitrary",
+            move |[_, signer, data, signature]| async move {
+                let signer = signer.as_string().unwrap_or_default();
+                let signature = Reflect::get(&signature, &"signature".into())?
+                    .as_string()
+                    .and_then(|signature| BASE64_STANDARD.decode(signature).ok())
+                    .unwrap_or_default();
+
+                let verified = adr36::verify_bytes(
+                    &MockKeplr::pub_key(),
+                    &signer,
+                    &data_bytes(&data),
+                    &signature,
+                )
+                .is_ok();
+
+                Ok(JsValue::from_bool(verified))
+            },
+        );
+
+        let mock = self.clone();
+        async_method(
+            &keplr,
+            "suggestToken",
+            move |[_, contract_address, viewing_key, _]| {
+                let mock = mock.clone();
+                async move {
+                    mock.approve()?;
+                    let contract_address = contract_address.as_string().unwrap_or_default();
+                    let viewing_key = viewing_key
+                        .as_string()
+                        .unwrap_or_else(|| format!("mock-key-{contract_address}"));
+                    mock.state
+                        .borrow_mut()
+                        .viewing_keys
+                        .insert(contract_address, viewing_key);
+                    Ok(JsValue::UNDEFINED)
+                }
+            },
+        );
+
+        let mock = self.clone();
+        async_method(
+            &keplr,
+            "getSecret20ViewingKey",
+            move |[chain_id, contract_address, ..]| {
+                let mock = mock.clone();
+                async move {
+                    mock.ensure_enabled(&chain_id)?;
+                    let contract_address = contract_address.as_string().unwrap_or_default();
+                    mock.viewing_key(&contract_address)
+                        .map(|key| JsValue::from_str(&key))
+                        .ok_or_else(|| js_error("There is no matched secret20"))
+                }
+            },
+        );
+
+        let mock = self.clone();
+        method(&keplr, "disable", move |[chain_id, ..]| {
+            match chain_id.as_string() {
+                Some(chain_id) => mock.state.borrow_mut().enabled.remove(&chain_id),
+                None => {
+                    mock.state.borrow_mut().enabled.clear();
+                    true
+                }
+            };
+            JsValue::UNDEFINED
+        });
+
+        keplr
+    }
+
+    fn offline_signer(&self, chain_id: JsValue) -> Object {
+        let signer = Object::new();
+        let _ = Reflect::set(&signer, &"chainId".into(), &chain_id);
+
+        let mock = self.clone();
+        async_method(&signer, "getAccounts", move |_| {
+            let mock = mock.clone();
+            let chain_id = chain_id.clone();
+            async move {
+                mock.ensure_enabled(&chain_id)?;
+
+                let account = Object::new();
+                let _ = Reflect::set(&account, &"address".into(), &MockKeplr::ADDRESS.into());
+                let _ = Reflect::set(&account, &"algo".into(), &"secp256k1".into());
+                let _ = Reflect::set(
+                    &account,
+                    &"pubkey".into(),
+                    &Uint8Array::from(MockKeplr::pub_key().as_slice()),
+                );
+
+                Ok(Array::of1(&account).into())
+            }
+        });
+
+        signer
+    }
+
+    /// Fails the way Keplr does when it's locked or the user closes the popup.
+    fn approve(&self) -> Result<(), JsValue> {
+        let options = self.state.borrow().options;
+        if options.locked {
+            Err(js_error("Keplr is locked"))
+        } else if options.reject {
+            Err(js_error("Request rejected"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ensure_enabled(&self, chain_id: &JsValue) -> Result<(), JsValue> {
+        if self.state.borrow().options.locked {
+            return Err(js_error("Keplr is locked"));
+        }
+
+        let chain_id = chain_id.as_string().unwrap_or_default();
+        match self.state.borrow().enabled.contains(&chain_id) {
+            true => Ok(()),
+            false => Err(js_error(&format!("{chain_id} is not enabled"))),
+        }
+    }
+}
+
+fn method<F>(target: &Object, name: &str, f: F)
+where
+    F: Fn(Args) -> JsValue + 'static,
+{
+    let closure =
+        Closure::<dyn Fn(JsValue, JsValue, JsValue, JsValue) -> JsValue>::new(move |a, b, c, d| {
+            f([a, b, c, d])
+        });
+    let _ = Reflect::set(target, &name.into(), closure.as_ref());
+    closure.forget();
+}
+
+fn async_method<F, Fut>(target: &Object, name: &str, f: F)
+where
+    F: Fn(Args) -> Fut + 'static,
+    Fut: Future<Output = Result<JsValue, JsValue>> + 'static,
+{
+    let closure =
+        Closure::<dyn Fn(JsValue, JsValue, JsValue, JsValue) -> Promise>::new(move |a, b, c, d| {
+            future_to_promise(f([a, b, c, d]))
+        });
+    let _ = Reflect::set(target, &name.into(), closure.as_ref());
+    closure.forget();
+}
+
+fn js_error(message: &str) -> JsValue {
+    js_sys::Error::new(message).into()
+}
+
+fn ensure_signer(signer: &JsValue) -> Result<(), JsValue> {
+    match signer.as_string().as_deref() == Some(MockKeplr::ADDRESS) {
+        true => Ok(()),
+        false => Err(js_error("Signer mismatched")),
+    }
+}
+
+fn data_bytes(data: &JsValue) -> Vec<u8> {
+    match data.as_string() {
+        Some(data) => data.into_bytes(),
+        None => Uint8Array::new(data).to_vec(),
+    }
+}
+
+fn key_js() -> JsValue {
+    let key = Object::new();
+    let set = |field: &str, value: &JsValue| {
+        let _ = Reflect::set(&key, &field.into(), value);
+    };
+
+    set("name", &MockKeplr::NAME.into());
+    set("algo", &"secp256k1".into());
+    set("pubKey", &Uint8Array::from(MockKeplr::pub_key().as_slice()));
+    set(
+        "address",
+        &Uint8Array::from(MockKeplr::address_bytes().as_slice()),
+    );
+    set("bech32Address", &MockKeplr::ADDRESS.into());
+    set("ethereumHexAddress", &"".into());
+    set("isNanoLedger", &false.into());
+    set("isKeystone", &false.into());
+
+    key.into()
+}
+
+fn std_signature(message: &[u8]) -> JsValue {
+    let signature: Signature = MockKeplr::signing_key().sign(message);
+
+    let pub_key = Object::new();
+    let _ = Reflect::set(
+        &pub_key,
+        &"type".into(),
+        &"tendermint/PubKeySecp256k1".into(),
+    );
+    let _ = Reflect::set(
+        &pub_key,
+        &"value".into(),
+        &BASE64_STANDARD.encode(MockKeplr::pub_key()).into(),
+    );
+
+    let std_signature = Object::new();
+    let _ = Reflect::set(&std_signature, &"pub_key".into(), &pub_key);
+    let _ = Reflect::set(
+        &std_signature,
+        &"signature".into(),
+        &BASE64_STANDARD.encode(signature.to_bytes()).into(),
+    );
+
+    std_signature.into()
+}
+
+fn sign_response(sign_doc: JsValue, message: &[u8]) -> Result<JsValue, JsValue> {
+    let response = Object::new();
+    Reflect::set(&response, &"signed".into(), &sign_doc)?;
+    Reflect::set(&response, &"signature".into(), &std_signature(message))?;
+    Ok(response.into())
+}
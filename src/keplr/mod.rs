@@ -1,9 +1,20 @@
+pub mod adr36;
+mod bindings;
+mod enigma;
 mod error;
 mod keplr;
+#[cfg(test)]
+mod mock;
+mod provider;
+pub mod suggest_chain_types;
 mod tests;
 pub mod tokens;
+#[cfg(test)]
+mod wasm_tests;
 
+pub use enigma::KeplrEnigmaUtils;
 pub use error::Error;
 pub use keplr::*;
 pub use keplr_sys;
+pub use provider::{Leap, Wallet, WalletProvider};
 pub use tests::KeplrTests;
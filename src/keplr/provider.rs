@@ -0,0 +1,335 @@
+//! Wallet extensions that inject a Keplr-compatible API under their own global.
+
+use super::{
+    bindings,
+    keplr::{sign_arbitrary_with, verify_arbitrary_with},
+    suggest_chain_types::ChainInfo,
+    Error, Keplr, KeplrOfflineSigner, KeplrOfflineSignerOnlyAmino, KeplrSigner, Key,
+};
+use async_trait::async_trait;
+use keplr_sys::EnigmaUtils;
+use rsecret::wallet::StdSignature;
+use serde::{Deserialize, Serialize};
+use web_sys::js_sys::JsString;
+
+/// The parts of the Keplr API the app relies on.
+#[async_trait(?Send)]
+pub trait WalletProvider {
+    /// Human readable name of the extension.
+    fn name(&self) -> &'static str;
+
+    fn is_available(&self) -> bool;
+
+    /// The `window` event fired when the user switches accounts in the extension.
+    fn keystore_change_event(&self) -> &'static str;
+
+    async fn enable(&self, chain_ids: Vec<String>) -> Result<(), Error>;
+
+    async fn get_key(&self, chain_id: &str) -> Result<Key, Error>;
+
+    fn get_offline_signer(&self, chain_id: &str) -> Result<KeplrOfflineSigner, Error>;
+
+    fn get_offline_signer_only_amino(
+        &self,
+        chain_id: &str,
+    ) -> Result<KeplrOfflineSignerOnlyAmino, Error>;
+
+    async fn get_offline_signer_auto(&self, chain_id: &str) -> Result<KeplrSigner, Error> {
+        let key = self.get_key(chain_id).await?;
+        // Hardware wallets can only sign amino (JSON) messages
+        let signer = match key.is_nano_ledger || key.is_keystone {
+            true => self.get_offline_signer_only_amino(chain_id)?.into(),
+            false => self.get_offline_signer(chain_id)?.into(),
+        };
+        Ok(signer)
+    }
+
+    fn get_enigma_utils(&self, chain_id: &str) -> Result<EnigmaUtils, Error>;
+
+    async fn suggest_chain(&self, chain_info: ChainInfo) -> Result<(), Error>;
+
+    async fn suggest_token(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+        viewing_key: Option<&str>,
+    ) -> Result<(), Error>;
+
+    async fn get_secret_20_viewing_key(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+    ) -> Result<String, Error>;
+
+    /// Signs `data` as an ADR-36 message, see [`Keplr::sign_arbitrary`].
+    async fn sign_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+    ) -> Result<StdSignature, Error>;
+
+    async fn verify_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+        signature: &StdSignature,
+    ) -> Result<bool, Error>;
+
+    fn disable(&self, chain_id: &str);
+}
+
+// the extension can be disabled or removed while the page is open
+fn ensure_keplr() -> Result<(), Error> {
+    match Keplr::is_available() {
+        true => Ok(()),
+        false => Err(Error::KeplrUnavailable),
+    }
+}
+
+#[async_trait(?Send)]
+impl WalletProvider for Keplr {
+    fn name(&self) -> &'static str {
+        "Keplr"
+    }
+
+    fn is_available(&self) -> bool {
+        Keplr::is_available()
+    }
+
+    fn keystore_change_event(&self) -> &'static str {
+        "keplr_keystorechange"
+    }
+
+    async fn enable(&self, chain_ids: Vec<String>) -> Result<(), Error> {
+        Keplr::enable(chain_ids).await
+    }
+
+    async fn get_key(&self, chain_id: &str) -> Result<Key, Error> {
+        Keplr::get_key(chain_id).await
+    }
+
+    fn get_offline_signer(&self, chain_id: &str) -> Result<KeplrOfflineSigner, Error> {
+        ensure_keplr()?;
+        Ok(Keplr::get_offline_signer(chain_id))
+    }
+
+    fn get_offline_signer_only_amino(
+        &self,
+        chain_id: &str,
+    ) -> Result<KeplrOfflineSignerOnlyAmino, Error> {
+        ensure_keplr()?;
+        Ok(Keplr::get_offline_signer_only_amino(chain_id))
+    }
+
+    fn get_enigma_utils(&self, chain_id: &str) -> Result<EnigmaUtils, Error> {
+        ensure_keplr()?;
+        Ok(Keplr::get_enigma_utils(chain_id))
+    }
+
+    async fn suggest_chain(&self, chain_info: ChainInfo) -> Result<(), Error> {
+        Keplr::suggest_chain(chain_info).await
+    }
+
+    async fn suggest_token(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+        viewing_key: Option<&str>,
+    ) -> Result<(), Error> {
+        Keplr::suggest_token(chain_id, contract_address, viewing_key).await
+    }
+
+    async fn get_secret_20_viewing_key(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+    ) -> Result<String, Error> {
+        Keplr::get_secret_20_viewing_key(chain_id, contract_address).await
+    }
+
+    async fn sign_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+    ) -> Result<StdSignature, Error> {
+        ensure_keplr()?;
+        Keplr::sign_arbitrary(chain_id, signer, data).await
+    }
+
+    async fn verify_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+        signature: &StdSignature,
+    ) -> Result<bool, Error> {
+        ensure_keplr()?;
+        Keplr::verify_arbitrary(chain_id, signer, data, signature).await
+    }
+
+    fn disable(&self, chain_id: &str) {
+        if ensure_keplr().is_ok() {
+            Keplr::disable(chain_id)
+        }
+    }
+}
+
+/// [Leap](https://www.leapwallet.io/), injected as `window.leap`.
+pub struct Leap {}
+
+impl Leap {
+    const GLOBAL: &'static str = "leap";
+
+    fn provider() -> Result<bindings::Provider, Error> {
+        bindings::injected(Self::GLOBAL).ok_or(Error::KeplrUnavailable)
+    }
+}
+
+#[async_trait(?Send)]
+impl WalletProvider for Leap {
+    fn name(&self) -> &'static str {
+        "Leap"
+    }
+
+    fn is_available(&self) -> bool {
+        bindings::injected(Self::GLOBAL).is_some()
+    }
+
+    fn keystore_change_event(&self) -> &'static str {
+        "leap_keystorechange"
+    }
+
+    async fn enable(&self, chain_ids: Vec<String>) -> Result<(), Error> {
+        Self::provider()?
+            .enable(chain_ids)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_key(&self, chain_id: &str) -> Result<Key, Error> {
+        let key = Self::provider()?.get_key(chain_id).await?;
+        Ok(serde_wasm_bindgen::from_value(key)?)
+    }
+
+    fn get_offline_signer(&self, chain_id: &str) -> Result<KeplrOfflineSigner, Error> {
+        let provider = Self::provider()?;
+        Ok(KeplrOfflineSigner::new(
+            provider.get_offline_signer(chain_id),
+            provider,
+        ))
+    }
+
+    fn get_offline_signer_only_amino(
+        &self,
+        chain_id: &str,
+    ) -> Result<KeplrOfflineSignerOnlyAmino, Error> {
+        let provider = Self::provider()?;
+        Ok(KeplrOfflineSignerOnlyAmino::new(
+            provider.get_offline_signer_only_amino(chain_id),
+            provider,
+        ))
+    }
+
+    fn get_enigma_utils(&self, chain_id: &str) -> Result<EnigmaUtils, Error> {
+        Ok(Self::provider()?.get_enigma_utils(chain_id))
+    }
+
+    async fn suggest_chain(&self, chain_info: ChainInfo) -> Result<(), Error> {
+        chain_info.validate()?;
+        let chain_info = serde_wasm_bindgen::to_value(&chain_info)?;
+        Self::provider()?
+            .suggest_chain(chain_info)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn suggest_token(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+        viewing_key: Option<&str>,
+    ) -> Result<(), Error> {
+        Self::provider()?
+            .suggest_token(
+                chain_id,
+                contract_address,
+                viewing_key.map(ToString::to_string),
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_secret_20_viewing_key(
+        &self,
+        chain_id: &str,
+        contract_address: &str,
+    ) -> Result<String, Error> {
+        Self::provider()?
+            .get_secret_20_viewing_key(chain_id, contract_address)
+            .await
+            .map(|key| JsString::from(key).into())
+            .map_err(Into::into)
+    }
+
+    async fn sign_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+    ) -> Result<StdSignature, Error> {
+        sign_arbitrary_with(&Self::provider()?, chain_id, signer, data).await
+    }
+
+    async fn verify_arbitrary(
+        &self,
+        chain_id: &str,
+        signer: &str,
+        data: &[u8],
+        signature: &StdSignature,
+    ) -> Result<bool, Error> {
+        verify_arbitrary_with(&Self::provider()?, chain_id, signer, data, signature).await
+    }
+
+    fn disable(&self, chain_id: &str) {
+        if let Ok(provider) = Self::provider() {
+            provider.disable(chain_id)
+        }
+    }
+}
+
+/// The wallets the user can pick from in the connect dialog.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Wallet {
+    #[default]
+    Keplr,
+    Leap,
+}
+
+impl Wallet {
+    pub const ALL: [Wallet; 2] = [Wallet::Keplr, Wallet::Leap];
+
+    pub fn provider(&self) -> &'static dyn WalletProvider {
+        match self {
+            Wallet::Keplr => &Keplr {},
+            Wallet::Leap => &Leap {},
+        }
+    }
+
+    /// The wallets that have injected their API into this page.
+    pub fn detected() -> Vec<Wallet> {
+        Self::ALL
+            .into_iter()
+            .filter(|wallet| wallet.provider().is_available())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Wallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.provider().name())
+    }
+}
@@ -0,0 +1,407 @@
+//! Types for `experimentalSuggestChain`, plus presets for the Secret networks.
+
+use super::Error;
+use serde::{Deserialize, Serialize};
+
+const SCRT_IMAGE_URL: &str =
+    "https://raw.githubusercontent.com/chainapsis/keplr-chain-registry/main/images/secret/chain.png";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainInfo {
+    pub chain_id: String,
+    pub chain_name: String,
+    pub rpc: String,
+    pub rest: String,
+    pub bip44: Bip44,
+    pub bech32_config: Bech32Config,
+    pub currencies: Vec<Currency>,
+    pub fee_currencies: Vec<FeeCurrency>,
+    pub stake_currency: Currency,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_symbol_image_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_url_for_staking: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_explorer: Option<TxExplorer>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub beta: bool,
+}
+
+impl ChainInfo {
+    /// Checks the things Keplr would otherwise reject (or worse, accept) after prompting the user.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: String| Err(Error::InvalidChainInfo(message));
+
+        if self.chain_id.is_empty() {
+            return invalid("chain_id is empty".to_string());
+        }
+        for (name, url) in [("rpc", &self.rpc), ("rest", &self.rest)] {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return invalid(format!("{name} is not an http(s) url: {url:?}"));
+            }
+        }
+        self.bech32_config.validate()?;
+        if self.currencies.is_empty() {
+            return invalid("currencies is empty".to_string());
+        }
+        if self.fee_currencies.is_empty() {
+            return invalid("fee_currencies is empty".to_string());
+        }
+        if !self
+            .currencies
+            .iter()
+            .any(|currency| currency.coin_minimal_denom == self.stake_currency.coin_minimal_denom)
+        {
+            return invalid(format!(
+                "stake currency {} is not listed in currencies",
+                self.stake_currency.coin_minimal_denom
+            ));
+        }
+        for fee_currency in &self.fee_currencies {
+            let GasPriceStep { low, average, high } = fee_currency.gas_price_step;
+            if !(0.0 <= low && low <= average && average <= high) {
+                return invalid(format!(
+                    "gas price steps for {} are out of order",
+                    fee_currency.coin_minimal_denom
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Bip44 {
+    pub coin_type: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Bech32Config {
+    pub bech32_prefix_acc_addr: String,
+    pub bech32_prefix_acc_pub: String,
+    pub bech32_prefix_val_addr: String,
+    pub bech32_prefix_val_pub: String,
+    pub bech32_prefix_cons_addr: String,
+    pub bech32_prefix_cons_pub: String,
+}
+
+impl Bech32Config {
+    /// Derives all the prefixes from the account prefix, the way the Cosmos SDK does.
+    pub fn from_acc_prefix(prefix: &str) -> Self {
+        Self {
+            bech32_prefix_acc_addr: prefix.to_string(),
+            bech32_prefix_acc_pub: format!("{prefix}pub"),
+            bech32_prefix_val_addr: format!("{prefix}valoper"),
+            bech32_prefix_val_pub: format!("{prefix}valoperpub"),
+            bech32_prefix_cons_addr: format!("{prefix}valcons"),
+            bech32_prefix_cons_pub: format!("{prefix}valconspub"),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        for prefix in [
+            &self.bech32_prefix_acc_addr,
+            &self.bech32_prefix_acc_pub,
+            &self.bech32_prefix_val_addr,
+            &self.bech32_prefix_val_pub,
+            &self.bech32_prefix_cons_addr,
+            &self.bech32_prefix_cons_pub,
+        ] {
+            // BIP-173: 1 to 83 characters in the range [33, 126], and not mixed case
+            let valid = (1..=83).contains(&prefix.len())
+                && prefix.bytes().all(|c| (33..=126).contains(&c))
+                && prefix.to_lowercase() == *prefix;
+            if !valid {
+                return Err(Error::InvalidChainInfo(format!(
+                    "invalid bech32 prefix: {prefix:?}"
+                )));
+            }
+        }
+
+        let acc = &self.bech32_prefix_acc_addr;
+        if !self.bech32_prefix_val_addr.starts_with(acc.as_str())
+            || !self.bech32_prefix_cons_addr.starts_with(acc.as_str())
+        {
+            return Err(Error::InvalidChainInfo(format!(
+                "validator prefixes don't extend the account prefix {acc:?}"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Currency {
+    pub coin_denom: String,
+    pub coin_minimal_denom: String,
+    pub coin_decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_gecko_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_image_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeCurrency {
+    pub coin_denom: String,
+    pub coin_minimal_denom: String,
+    pub coin_decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_gecko_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_image_url: Option<String>,
+    pub gas_price_step: GasPriceStep,
+}
+
+impl FeeCurrency {
+    pub fn new(currency: Currency, gas_price_step: GasPriceStep) -> Self {
+        Self {
+            coin_denom: currency.coin_denom,
+            coin_minimal_denom: currency.coin_minimal_denom,
+            coin_decimals: currency.coin_decimals,
+            coin_gecko_id: currency.coin_gecko_id,
+            coin_image_url: currency.coin_image_url,
+            gas_price_step,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GasPriceStep {
+    pub low: f64,
+    pub average: f64,
+    pub high: f64,
+}
+
+impl GasPriceStep {
+    /// Secret Network's, on every network. Both the app's fees and the chains it suggests use
+    /// these.
+    pub const SCRT: GasPriceStep = GasPriceStep {
+        low: 0.1,
+        average: 0.25,
+        high: 0.5,
+    };
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TxExplorer {
+    pub name: String,
+    /// Url with a `{txHash}` placeholder.
+    pub tx_url: String,
+}
+
+pub struct ChainInfoBuilder {
+    chain_id: Option<String>,
+    chain_name: Option<String>,
+    rpc: Option<String>,
+    rest: Option<String>,
+    coin_type: u32,
+    bech32_config: Bech32Config,
+    currencies: Vec<Currency>,
+    fee_currencies: Vec<FeeCurrency>,
+    stake_currency: Option<Currency>,
+    features: Vec<String>,
+    chain_symbol_image_url: Option<String>,
+    wallet_url_for_staking: Option<String>,
+    tx_explorer: Option<TxExplorer>,
+    beta: bool,
+}
+
+impl ChainInfoBuilder {
+    pub fn new() -> Self {
+        ChainInfoBuilder {
+            chain_id: None,
+            chain_name: None,
+            rpc: None,
+            rest: None,
+            coin_type: 529,
+            bech32_config: Bech32Config::from_acc_prefix("secret"),
+            currencies: vec![],
+            fee_currencies: vec![],
+            stake_currency: None,
+            features: vec![],
+            chain_symbol_image_url: None,
+            wallet_url_for_staking: None,
+            tx_explorer: None,
+            beta: false,
+        }
+    }
+
+    /// Secret Network mainnet (`secret-4`).
+    pub fn mainnet() -> Self {
+        Self::new()
+            .chain_id("secret-4")
+            .chain_name("Secret Network")
+            .rpc("https://rpc.mainnet.secretsaturn.net")
+            .rest("https://lcd.mainnet.secretsaturn.net")
+            .scrt(GasPriceStep::SCRT)
+            .features(["secretwasm", "ibc-go", "ibc-transfer"])
+            .chain_symbol_image_url(SCRT_IMAGE_URL)
+            .wallet_url_for_staking("https://wallet.keplr.app/chains/secret-network")
+            .tx_explorer("Mintscan", "https://www.mintscan.io/secret/tx/{txHash}")
+    }
+
+    /// Secret Network testnet (`pulsar-3`).
+    pub fn pulsar() -> Self {
+        Self::new()
+            .chain_id("pulsar-3")
+            .chain_name("Secret Network Testnet")
+            .rpc("https://rpc.pulsar.scrttestnet.com")
+            .rest("https://api.pulsar.scrttestnet.com")
+            .scrt(GasPriceStep::SCRT)
+            .features(["secretwasm", "ibc-go", "ibc-transfer"])
+            .chain_symbol_image_url(SCRT_IMAGE_URL)
+            .tx_explorer("Ping.pub", "https://testnet.ping.pub/secret/tx/{txHash}")
+            .beta(true)
+    }
+
+    /// A local development chain (`secretdev-1`), as started by the `localsecret` docker image.
+    pub fn localsecret() -> Self {
+        Self::new()
+            .chain_id("secretdev-1")
+            .chain_name("LocalSecret")
+            .rpc("http://127.0.0.1:26657")
+            .rest("http://127.0.0.1:1317")
+            .scrt(GasPriceStep::SCRT)
+            .features(["secretwasm"])
+            .chain_symbol_image_url(SCRT_IMAGE_URL)
+            .beta(true)
+    }
+
+    pub fn chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = Some(chain_id.to_string());
+        self
+    }
+
+    pub fn chain_name(mut self, chain_name: &str) -> Self {
+        self.chain_name = Some(chain_name.to_string());
+        self
+    }
+
+    pub fn rpc(mut self, rpc: &str) -> Self {
+        self.rpc = Some(rpc.to_string());
+        self
+    }
+
+    pub fn rest(mut self, rest: &str) -> Self {
+        self.rest = Some(rest.to_string());
+        self
+    }
+
+    pub fn coin_type(mut self, coin_type: u32) -> Self {
+        self.coin_type = coin_type;
+        self
+    }
+
+    pub fn bech32_prefix(mut self, prefix: &str) -> Self {
+        self.bech32_config = Bech32Config::from_acc_prefix(prefix);
+        self
+    }
+
+    pub fn bech32_config(mut self, bech32_config: Bech32Config) -> Self {
+        self.bech32_config = bech32_config;
+        self
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currencies.push(currency);
+        self
+    }
+
+    pub fn fee_currency(mut self, fee_currency: FeeCurrency) -> Self {
+        self.fee_currencies.push(fee_currency);
+        self
+    }
+
+    pub fn stake_currency(mut self, stake_currency: Currency) -> Self {
+        self.stake_currency = Some(stake_currency);
+        self
+    }
+
+    /// Uses SCRT as the only currency, for fees and for staking.
+    pub fn scrt(self, gas_price_step: GasPriceStep) -> Self {
+        let scrt = Currency {
+            coin_denom: "SCRT".to_string(),
+            coin_minimal_denom: "uscrt".to_string(),
+            coin_decimals: 6,
+            coin_gecko_id: Some("secret".to_string()),
+            coin_image_url: Some(SCRT_IMAGE_URL.to_string()),
+        };
+
+        self.currency(scrt.clone())
+            .fee_currency(FeeCurrency::new(scrt.clone(), gas_price_step))
+            .stake_currency(scrt)
+    }
+
+    pub fn features<I, S>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        self.features = features.into_iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    pub fn chain_symbol_image_url(mut self, url: &str) -> Self {
+        self.chain_symbol_image_url = Some(url.to_string());
+        self
+    }
+
+    pub fn wallet_url_for_staking(mut self, url: &str) -> Self {
+        self.wallet_url_for_staking = Some(url.to_string());
+        self
+    }
+
+    pub fn tx_explorer(mut self, name: &str, tx_url: &str) -> Self {
+        self.tx_explorer = Some(TxExplorer {
+            name: name.to_string(),
+            tx_url: tx_url.to_string(),
+        });
+        self
+    }
+
+    pub fn beta(mut self, beta: bool) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    pub fn build(self) -> Result<ChainInfo, Error> {
+        let missing = |field: &str| Error::InvalidChainInfo(format!("{field} is required"));
+
+        let chain_info = ChainInfo {
+            chain_id: self.chain_id.ok_or_else(|| missing("chain_id"))?,
+            chain_name: self.chain_name.ok_or_else(|| missing("chain_name"))?,
+            rpc: self.rpc.ok_or_else(|| missing("rpc"))?,
+            rest: self.rest.ok_or_else(|| missing("rest"))?,
+            bip44: Bip44 {
+                coin_type: self.coin_type,
+            },
+            bech32_config: self.bech32_config,
+            currencies: self.currencies,
+            fee_currencies: self.fee_currencies,
+            stake_currency: self
+                .stake_currency
+                .ok_or_else(|| missing("stake_currency"))?,
+            features: self.features,
+            chain_symbol_image_url: self.chain_symbol_image_url,
+            wallet_url_for_staking: self.wallet_url_for_staking,
+            tx_explorer: self.tx_explorer,
+            beta: self.beta,
+        };
+
+        chain_info.validate()?;
+
+        Ok(chain_info)
+    }
+}
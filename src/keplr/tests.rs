@@ -1,5 +1,5 @@
+use crate::config::ChainConfig;
 use crate::keplr::{suggest_chain_types::*, AccountData, Keplr, Key};
-use crate::CHAIN_ID;
 use keplr_sys; // normally you wouldn't use keplr_sys directly
 use leptos::prelude::*;
 use leptos::web_sys::console;
@@ -75,20 +75,23 @@ pub fn KeplrTests() -> impl IntoView {
         log!("cleaning up <KeplrTests/>");
     });
 
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
+    let chain_id = move || chain.get_untracked().chain_id;
+
     let enable_keplr_action: Action<(), bool, SyncStorage> =
-        Action::new_unsync_with_value(Some(false), |_: &()| enable_keplr(CHAIN_ID));
+        Action::new_unsync_with_value(Some(false), move |_: &()| enable_keplr(chain_id()));
     let get_account_action: Action<(), AccountData, SyncStorage> =
-        Action::new_unsync(|_: &()| get_account(CHAIN_ID));
+        Action::new_unsync(move |_: &()| get_account(chain_id()));
     let get_key_action: Action<(), Key, SyncStorage> =
-        Action::new_unsync(|_: &()| get_key(CHAIN_ID));
+        Action::new_unsync(move |_: &()| get_key(chain_id()));
     let get_viewing_key_action: Action<String, String, SyncStorage> =
-        Action::new_unsync(|input: &String| {
+        Action::new_unsync(move |input: &String| {
             let token_address = input.clone();
-            get_secret_20_viewing_key(CHAIN_ID, token_address)
+            get_secret_20_viewing_key(chain_id(), token_address)
         });
     let suggest_token_action: Action<(), (), SyncStorage> = Action::new_unsync(move |_: &()| {
         suggest_token(
-            CHAIN_ID,
+            chain_id(),
             "secret1s09x2xvfd2lp2skgzm29w2xtena7s8fq98v852",
             Some("hola"),
         )
@@ -118,9 +121,9 @@ pub fn KeplrTests() -> impl IntoView {
 
     // Non-Actions
 
-    let get_enigma_utils = move |_| get_enigma_utils(CHAIN_ID);
+    let get_enigma_utils = move |_| get_enigma_utils(chain_id());
     let disable_keplr = move |_| {
-        keplr_sys::disable(CHAIN_ID);
+        keplr_sys::disable(chain_id());
         enable_keplr_action.value().set(Some(false));
         info!("Keplr Disabled");
     };
@@ -182,45 +185,13 @@ pub fn KeplrTests() -> impl IntoView {
 }
 
 pub async fn suggest() {
-    let chain_info = SuggestingChainInfo {
-        chain_id: "secretdev-1".to_string(),
-        chain_name: "localsecret".to_string(),
-        rpc: "http://127.0.0.1:26657".to_string(),
-        rest: "http://127.0.0.1:1317".to_string(),
-        bip44: Bip44 { coin_type: 529 },
-        bech32_config: Bech32Config {
-            bech32_prefix_acc_addr: "secret".to_string(),
-            bech32_prefix_acc_pub: "secretpub".to_string(),
-            bech32_prefix_val_addr: "secretvaloper".to_string(),
-            bech32_prefix_val_pub: "secretvaloperpub".to_string(),
-            bech32_prefix_cons_addr: "secretvalcons".to_string(),
-            bech32_prefix_cons_pub: "secretvalconspub".to_string(),
-        },
-        currencies: vec![Currency {
-            coin_denom: "SCRT".to_string(),
-            coin_minimal_denom: "uscrt".to_string(),
-            coin_decimals: 6,
-            coin_gecko_id: "secret".to_string(),
-        }],
-        fee_currencies: vec![FeeCurrency {
-            coin_denom: "SCRT".to_string(),
-            coin_minimal_denom: "uscrt".to_string(),
-            coin_decimals: 6,
-            coin_gecko_id: "secret".to_string(),
-            gas_price_step: GasPriceStep {
-                low: 0.1,
-                average: 0.25,
-                high: 0.5,
-            },
-        }],
-        stake_currency: Currency {
-            coin_denom: "SCRT".to_string(),
-            coin_minimal_denom: "uscrt".to_string(),
-            coin_decimals: 6,
-            coin_gecko_id: "secret".to_string(),
-        },
+    let result = match ChainInfoBuilder::localsecret().build() {
+        Ok(chain_info) => Keplr::suggest_chain(chain_info).await,
+        Err(error) => Err(error),
     };
 
-    let chain_info_js = serde_wasm_bindgen::to_value(&chain_info).unwrap();
-    let _ = keplr_sys::suggest_chain(chain_info_js).await;
+    match result {
+        Ok(_) => log!("chain suggested"),
+        Err(ref e) => log!("{e}"),
+    }
 }
@@ -0,0 +1,323 @@
+//! Run with `wasm-pack test --headless --firefox` (or `--chrome`).
+
+use super::{
+    adr36,
+    mock::{MockKeplr, MockOptions},
+    Error, Keplr, KeplrSigner, Wallet, WalletProvider,
+};
+use crate::{
+    config::Network,
+    permit::{Permission, Permit},
+    state::{AccountCache, TokenMap},
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use rsecret::wallet::{SignDocVariant, Signer, StdSignDoc};
+use secretrs::tx::SignDoc;
+use serde_json::{json, Value};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const CHAIN_ID: &str = "secret-4";
+
+async fn enabled_mock() -> MockKeplr {
+    let mock = MockKeplr::install();
+    Keplr::enable(vec![CHAIN_ID.to_string()])
+        .await
+        .expect("enable failed");
+    mock
+}
+
+fn amino_sign_doc() -> Value {
+    json!({
+        "chain_id": CHAIN_ID,
+        "account_number": "42",
+        "sequence": "7",
+        "fee": {
+            "amount": [{ "denom": "uscrt", "amount": "2500" }],
+            "gas": "100000",
+        },
+        "msgs": [{
+            "type": "cosmos-sdk/MsgSend",
+            "value": {
+                "from_address": MockKeplr::ADDRESS,
+                "to_address": MockKeplr::ADDRESS,
+                "amount": [{ "denom": "uscrt", "amount": "1" }],
+            },
+        }],
+        "memo": "hello",
+    })
+}
+
+fn direct_sign_doc() -> SignDoc {
+    SignDoc {
+        body_bytes: vec![1, 2, 3],
+        auth_info_bytes: vec![4, 5, 6],
+        chain_id: CHAIN_ID.to_string(),
+        account_number: 42,
+    }
+}
+
+#[wasm_bindgen_test]
+fn detects_the_mock() {
+    MockKeplr::install();
+    assert!(Keplr::is_available());
+    assert!(Wallet::detected().contains(&Wallet::Keplr));
+
+    MockKeplr::uninstall();
+    assert!(!Keplr::is_available());
+}
+
+#[wasm_bindgen_test]
+async fn missing_extension_is_an_error() {
+    MockKeplr::uninstall();
+
+    for wallet in Wallet::ALL {
+        let provider = wallet.provider();
+        assert!(provider.get_offline_signer(CHAIN_ID).is_err());
+        assert!(provider.get_offline_signer_only_amino(CHAIN_ID).is_err());
+        assert!(provider.get_enigma_utils(CHAIN_ID).is_err());
+        assert!(provider.get_offline_signer_auto(CHAIN_ID).await.is_err());
+        assert!(provider
+            .sign_arbitrary(CHAIN_ID, MockKeplr::ADDRESS, b"data")
+            .await
+            .is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+async fn enable_and_get_key() {
+    enabled_mock().await;
+
+    let key = Keplr::get_key(CHAIN_ID).await.unwrap();
+    assert_eq!(key.bech32_address, MockKeplr::ADDRESS);
+    assert_eq!(key.pub_key, MockKeplr::pub_key());
+    assert!(!key.is_nano_ledger);
+}
+
+#[wasm_bindgen_test]
+async fn get_key_requires_enable() {
+    MockKeplr::install();
+    assert!(Keplr::get_key(CHAIN_ID).await.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn enable_rejected() {
+    MockKeplr::install_with(MockOptions {
+        reject: true,
+        ..Default::default()
+    });
+
+    let result = Keplr::enable(vec![CHAIN_ID.to_string()]).await;
+    assert_eq!(result, Err(Error::RequestRejected));
+}
+
+#[wasm_bindgen_test]
+async fn locked() {
+    let mock = enabled_mock().await;
+    mock.set_options(MockOptions {
+        locked: true,
+        ..Default::default()
+    });
+
+    assert!(Keplr::get_key(CHAIN_ID).await.is_err());
+    assert!(Keplr::get_account(CHAIN_ID).await.is_err());
+
+    mock.set_options(MockOptions::default());
+    assert!(Keplr::get_key(CHAIN_ID).await.is_ok());
+}
+
+#[wasm_bindgen_test]
+async fn get_account() {
+    enabled_mock().await;
+
+    let account = Keplr::get_account(CHAIN_ID).await.unwrap();
+    assert_eq!(account.address, MockKeplr::ADDRESS);
+}
+
+#[wasm_bindgen_test]
+async fn viewing_keys() {
+    let mock = enabled_mock().await;
+    let contract = "secret1s09x2xvfd2lp2skgzm29w2xtena7s8fq98v852";
+
+    assert!(Keplr::get_secret_20_viewing_key(CHAIN_ID, contract)
+        .await
+        .is_err());
+
+    Keplr::suggest_token(CHAIN_ID, contract, Some("hola"))
+        .await
+        .unwrap();
+
+    assert_eq!(mock.viewing_key(contract).as_deref(), Some("hola"));
+    assert_eq!(
+        Keplr::get_secret_20_viewing_key(CHAIN_ID, contract).await,
+        Ok("hola".to_string())
+    );
+}
+
+#[wasm_bindgen_test]
+async fn auto_signer_is_direct() {
+    enabled_mock().await;
+
+    let signer = Keplr::get_offline_signer_auto(CHAIN_ID).await.unwrap();
+    assert!(matches!(signer, KeplrSigner::Direct(_)));
+    assert_eq!(signer.chain_id(), CHAIN_ID);
+}
+
+#[wasm_bindgen_test]
+async fn sign_amino() {
+    enabled_mock().await;
+    let sign_doc: StdSignDoc = serde_json::from_value(amino_sign_doc()).unwrap();
+
+    for signer in [
+        KeplrSigner::from(Keplr::get_offline_signer(CHAIN_ID)),
+        KeplrSigner::from(Keplr::get_offline_signer_only_amino(CHAIN_ID)),
+    ] {
+        let response = signer
+            .sign_amino(MockKeplr::ADDRESS, sign_doc.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&response.signed).unwrap(),
+            amino_sign_doc()
+        );
+        assert_eq!(
+            serde_json::to_value(&response.signature).unwrap()["pub_key"]["value"],
+            BASE64_STANDARD.encode(MockKeplr::pub_key())
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn sign_amino_wrong_signer() {
+    enabled_mock().await;
+    let sign_doc: StdSignDoc = serde_json::from_value(amino_sign_doc()).unwrap();
+    let other = "secret1s09x2xvfd2lp2skgzm29w2xtena7s8fq98v852";
+
+    let result = Keplr::get_offline_signer_only_amino(CHAIN_ID)
+        .sign_amino(other, sign_doc)
+        .await;
+
+    assert_eq!(result.err(), Some(Error::SignerMismatch(other.to_string())));
+}
+
+#[wasm_bindgen_test]
+async fn sign_amino_rejected() {
+    let mock = enabled_mock().await;
+    mock.set_options(MockOptions {
+        reject: true,
+        ..Default::default()
+    });
+    let sign_doc: StdSignDoc = serde_json::from_value(amino_sign_doc()).unwrap();
+
+    let result = Keplr::get_offline_signer(CHAIN_ID)
+        .sign_amino(MockKeplr::ADDRESS, sign_doc)
+        .await;
+
+    assert_eq!(result.err(), Some(Error::RequestRejected));
+}
+
+#[wasm_bindgen_test]
+async fn sign_direct() {
+    enabled_mock().await;
+
+    let response = Keplr::get_offline_signer(CHAIN_ID)
+        .sign_direct(
+            MockKeplr::ADDRESS,
+            SignDocVariant::SignDoc(direct_sign_doc()),
+        )
+        .await
+        .unwrap();
+
+    let SignDocVariant::SignDoc(signed) = response.signed else {
+        panic!("expected a SignDoc");
+    };
+    let expected = direct_sign_doc();
+    assert_eq!(signed.body_bytes, expected.body_bytes);
+    assert_eq!(signed.auth_info_bytes, expected.auth_info_bytes);
+    assert_eq!(signed.chain_id, expected.chain_id);
+    assert_eq!(signed.account_number, expected.account_number);
+}
+
+#[wasm_bindgen_test]
+async fn only_amino_cannot_sign_direct() {
+    enabled_mock().await;
+
+    let result = Keplr::get_offline_signer_only_amino(CHAIN_ID)
+        .sign_direct(
+            MockKeplr::ADDRESS,
+            SignDocVariant::SignDoc(direct_sign_doc()),
+        )
+        .await;
+
+    assert_eq!(result.err(), Some(Error::UnsupportedSignMode));
+}
+
+#[wasm_bindgen_test]
+async fn sign_and_verify_arbitrary() {
+    enabled_mock().await;
+    let key = Keplr::get_key(CHAIN_ID).await.unwrap();
+    let data = b"sign in to secret-leptos";
+
+    let signature = Keplr::sign_arbitrary(CHAIN_ID, MockKeplr::ADDRESS, data)
+        .await
+        .unwrap();
+
+    assert_eq!(adr36::verify(&key, data, &signature), Ok(()));
+    assert!(adr36::verify(&key, b"something else", &signature).is_err());
+    assert_eq!(
+        Keplr::verify_arbitrary(CHAIN_ID, MockKeplr::ADDRESS, data, &signature).await,
+        Ok(true)
+    );
+
+    // only Keplr is installed, so Leap's requests mustn't end up in `window.keplr`
+    assert!(Wallet::Leap
+        .provider()
+        .sign_arbitrary(CHAIN_ID, MockKeplr::ADDRESS, data)
+        .await
+        .is_err());
+}
+
+#[wasm_bindgen_test]
+async fn permit_unlocks_registry_tokens() {
+    enabled_mock().await;
+    let tokens = TokenMap::new(&Network::Mainnet.config());
+    let signer = Keplr::get_offline_signer_auto(CHAIN_ID).await.unwrap();
+
+    let permit = Permit::sign_for_registry(
+        &signer,
+        MockKeplr::ADDRESS,
+        CHAIN_ID,
+        &tokens,
+        vec![Permission::Balance],
+    )
+    .await
+    .unwrap();
+    assert_eq!(permit.params.chain_id, CHAIN_ID);
+    assert_eq!(
+        permit.signature.pub_key.value,
+        BASE64_STANDARD.encode(MockKeplr::pub_key())
+    );
+
+    // the portfolio reads balances with whatever permit the cache has for the token
+    let cache = AccountCache::new();
+    let contract = tokens.keys().next().expect("the registry is empty");
+    assert_eq!(cache.permit(CHAIN_ID, MockKeplr::ADDRESS, contract), None);
+
+    cache.set_permit(MockKeplr::ADDRESS, permit.clone());
+    for contract in tokens.keys() {
+        assert_eq!(
+            cache
+                .permit(CHAIN_ID, MockKeplr::ADDRESS, contract)
+                .as_ref(),
+            Some(&permit)
+        );
+    }
+    assert_eq!(
+        cache.permit(CHAIN_ID, MockKeplr::ADDRESS, MockKeplr::ADDRESS),
+        None
+    );
+    // signed for secret-4 only
+    assert_eq!(cache.permit("pulsar-3", MockKeplr::ADDRESS, contract), None);
+}
@@ -8,13 +8,14 @@ use leptos::{
     html::{Dialog, Input},
     logging::log,
     prelude::*,
+    task::spawn_local,
 };
 use leptos_router::components::{Route, Router, Routes, A};
 use leptos_router_macro::path;
 use secret_toolkit_snip20::{QueryMsg, TokenInfoResponse};
 use send_wrapper::SendWrapper;
 use tonic_web_wasm_client::Client;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use web_sys::{js_sys, wasm_bindgen::JsValue};
 
 use rsecret::{
@@ -22,43 +23,39 @@ use rsecret::{
     secret_network_client::CreateQuerierOptions,
 };
 
+mod code_hash;
+mod coin;
 mod components;
-mod constants;
+mod config;
+mod contract;
+mod endpoints;
+mod enigma;
 mod error;
+mod fee;
+mod history;
 mod keplr;
+mod permit;
 mod prelude;
+mod query_cache;
+mod snip20;
 mod state;
+mod storage;
+mod tx;
+mod tx_tracker;
 mod utils;
+mod viewing_keys;
 
-use components::Spinner2;
-use constants::{CHAIN_ID, GRPC_URL};
+use code_hash::CodeHashes;
+use components::{ConnectDialog, History, Portfolio, SendTokens, Spinner2, TxToasts};
+use config::{ChainConfig, Network};
+use endpoints::HEALTH_CHECK_INTERVAL;
 use error::Error;
-use keplr::{keplr_sys, Keplr, KeplrTests, Key};
-use state::{KeplrSignals, TokenMap, WasmClient};
+use keplr::{keplr_sys, Keplr, KeplrTests, Key, Wallet, WalletProvider};
+use query_cache::{cached_query, CacheKey};
+use state::{KeplrSignals, SavedConnection, TokenMap, WasmClient};
+use tx_tracker::TxTracker;
 
-// TODO: move custom types to seperate module
-
-// TODO: include the decimals somehow, and use that in the Display trait
-#[derive(Clone, Debug)]
-pub struct Coin {
-    pub denom: String,
-    pub amount: String,
-}
-
-impl From<secretrs::proto::cosmos::base::v1beta1::Coin> for Coin {
-    fn from(value: secretrs::proto::cosmos::base::v1beta1::Coin) -> Self {
-        Self {
-            denom: value.denom,
-            amount: value.amount,
-        }
-    }
-}
-
-impl std::fmt::Display for Coin {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.amount, self.denom)
-    }
-}
+pub use coin::{Amount, AmountError, Coin, DenomInfo};
 
 #[component]
 pub fn App() -> impl IntoView {
@@ -66,25 +63,25 @@ pub fn App() -> impl IntoView {
 
     // Global Context
 
-    let keplr = KeplrSignals::new();
-    let wasm_client = WasmClient::new();
-    let token_map = TokenMap::new();
-    debug!("Loaded {} tokens", token_map.len());
+    let chain = RwSignal::new(Network::load().config());
+    let keplr = KeplrSignals::new(chain);
+    let wasm_client = WasmClient::new(&chain.get_untracked());
+    // the registry is reloaded whenever the network changes
+    let token_map = Memo::new(move |_| TokenMap::new(&chain.get()));
+    let code_hashes = CodeHashes::new(chain, wasm_client);
+    let tx_tracker = TxTracker::new(wasm_client);
 
+    provide_context(chain);
     provide_context(keplr);
     provide_context(wasm_client);
     provide_context(token_map);
+    provide_context(code_hashes);
+    provide_context(tx_tracker);
 
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
     let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
     let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
-    let token_map = use_context::<TokenMap>().expect("tokens context missing!");
-
-    // Event Listeners
-
-    let keplr_keystorechange_handle =
-        window_event_listener_untyped("keplr_keystorechange", move |_| {
-            warn!("Key store in Keplr is changed. You may need to refetch the account info.");
-        });
+    let token_map = use_context::<Memo<TokenMap>>().expect("tokens context missing!");
 
     // let update_grpc_url = move |_| {
     //     debug!("updating client_options.grpc_url");
@@ -93,38 +90,94 @@ pub fn App() -> impl IntoView {
 
     // Actions
 
-    let enable_keplr_action: Action<(), bool, SyncStorage> =
-        Action::new_unsync_with_value(Some(false), move |_: &()| async move {
-            let keplr_extension = js_sys::Reflect::get(&window(), &JsValue::from_str("keplr"))
-                .expect("unable to check for `keplr` property");
-
-            if keplr_extension.is_undefined() || keplr_extension.is_null() {
-                window()
-                    .alert_with_message("keplr not found")
-                    .expect("alert failed");
-                keplr.enabled.set(false);
-                false
-            } else {
-                debug!("Trying to enable Keplr...");
-                match Keplr::enable(vec![CHAIN_ID.to_string()]).await {
-                    Ok(_) => {
-                        keplr.enabled.set(true);
-                        debug!("Keplr is enabled");
-                        true
-                    }
-                    Err(e) => {
-                        keplr.enabled.set(false);
-                        error!("{e}");
-                        false
+    let enable_keplr_action: Action<Wallet, bool, SyncStorage> =
+        Action::new_unsync_with_value(Some(false), move |wallet: &Wallet| {
+            let wallet = *wallet;
+            let config = chain.get_untracked();
+            async move {
+                let provider = wallet.provider();
+
+                if !provider.is_available() {
+                    utils::alert(format!("{wallet} not found"));
+                    keplr.enabled.set(false);
+                    false
+                } else {
+                    debug!("Trying to enable {wallet}...");
+                    match config.enable(wallet).await {
+                        Ok(_) => {
+                            keplr.wallet.set(wallet);
+                            keplr.enabled.set(true);
+                            SavedConnection::new(wallet, config.chain_id).save();
+                            debug!("{wallet} is enabled");
+                            true
+                        }
+                        Err(e) => {
+                            keplr.enabled.set(false);
+                            error!("{e}");
+                            false
+                        }
                     }
                 }
             }
         });
 
+    // The wallet from the previous visit. `enable` opens a popup whenever the wallet is locked,
+    // even for an approved origin, so it isn't called on page load. Reading the key instead
+    // fails quietly unless the origin is approved and the wallet unlocked; if it works, the
+    // connection is restored silently, otherwise the wallet is offered as a one-click reconnect.
+    let saved_wallet = RwSignal::new(SavedConnection::load().and_then(|saved| {
+        let matches = saved.chain_id == chain.get_untracked().chain_id;
+        (matches && saved.wallet.provider().is_available()).then_some(saved.wallet)
+    }));
+    if let Some(wallet) = saved_wallet.get_untracked() {
+        let chain_id = chain.get_untracked().chain_id;
+        spawn_local(async move {
+            debug!("Reconnecting to {wallet}...");
+            match wallet.provider().get_key(chain_id).await {
+                Ok(_) => {
+                    keplr.wallet.set(wallet);
+                    keplr.enabled.set(true);
+                    saved_wallet.set(None);
+                    debug!("{wallet} is enabled");
+                }
+                Err(e) => debug!("{wallet} needs approval to reconnect: {e}"),
+            }
+        });
+    }
+
+    // Probe the endpoint pool now and periodically, so failover has fresh data to go on
+    spawn_local(async move { wasm_client.check_health().await });
+    let health_check_handle = set_interval_with_handle(
+        move || spawn_local(async move { wasm_client.check_health().await }),
+        HEALTH_CHECK_INTERVAL,
+    )
+    .ok();
+
+    // Node references
+
+    let options_dialog_ref = NodeRef::<Dialog>::new();
+    let connect_dialog_ref = NodeRef::<Dialog>::new();
+
     // on:click handlers
 
-    let enable_keplr = move |_| {
-        enable_keplr_action.dispatch(());
+    let enable_keplr = move |_| match connect_dialog_ref.get() {
+        Some(dialog) => {
+            let _ = dialog.show_modal();
+        }
+        None => {
+            let _ = window().alert_with_message("Something is wrong!");
+        }
+    };
+
+    let select_wallet = move |wallet: Wallet| {
+        enable_keplr_action.dispatch(wallet);
+    };
+
+    let reconnect = move |_| {
+        if let Some(wallet) = saved_wallet.get_untracked() {
+            saved_wallet.set(None);
+            enable_keplr_action.dispatch(wallet);
+        }
     };
 
     // let disable_keplr = move |_| {
@@ -133,10 +186,6 @@ pub fn App() -> impl IntoView {
     //     keplr.key.set(None);
     // };
 
-    // Node references
-
-    let options_dialog_ref = NodeRef::<Dialog>::new();
-
     // Effects
 
     // open the dialog whenever the "enable_keplr_action" is pending
@@ -152,7 +201,9 @@ pub fn App() -> impl IntoView {
 
     Owner::on_cleanup(move || {
         info!("cleaning up <Aoo/>");
-        keplr_keystorechange_handle.remove()
+        if let Some(handle) = health_check_handle {
+            handle.clear()
+        }
     });
 
     // HTML Elements
@@ -170,6 +221,14 @@ pub fn App() -> impl IntoView {
     };
 
     let key_name = move || keplr.key.get().and_then(Result::ok).map(|key| key.name);
+    let switched_to = move || keplr.account_switched.get();
+    let active_endpoint = move || {
+        let url = wasm_client.url.get();
+        match wasm_client.active().and_then(|status| status.height) {
+            Some(height) => format!("{url} @ {height}"),
+            None => url,
+        }
+    };
 
     view! {
         <Router>
@@ -188,35 +247,70 @@ pub fn App() -> impl IntoView {
                         when=move || keplr.enabled.get()
                         fallback=move || {
                             view! {
-                                <button
-                                    on:click=enable_keplr
-                                    disabled=enable_keplr_action.pending()
-                                >
-                                    Connect Wallet
-                                </button>
+                                <div class="flex gap-2">
+                                    {move || {
+                                        saved_wallet
+                                            .get()
+                                            .map(|wallet| {
+                                                view! {
+                                                    <button
+                                                        on:click=reconnect
+                                                        disabled=enable_keplr_action.pending()
+                                                    >
+                                                        {format!("Reconnect {wallet}")}
+                                                    </button>
+                                                }
+                                            })
+                                    }}
+                                    <button
+                                        on:click=enable_keplr
+                                        disabled=enable_keplr_action.pending()
+                                    >
+                                        Connect Wallet
+                                    </button>
+                                </div>
                             }
                         }
                     >
                         <button on:click=toggle_options_menu>"Options"</button>
                     </Show>
                 </div>
+                <p class="text-xs text-neutral-500">{active_endpoint}</p>
                 <hr />
                 <nav>
                     <A href="/secret-leptos/">"Home"</A>
+                    <A href="/secret-leptos/portfolio">"Portfolio"</A>
+                    <A href="/secret-leptos/send">"Send"</A>
+                    <A href="/secret-leptos/history">"History"</A>
                     <A href="/secret-leptos/keplr">"Keplr"</A>
                 </nav>
                 <hr />
+                <Show when=move || keplr.account_switched.get().is_some()>
+                    <div class="flex justify-between items-center text-sm">
+                        <p>"Switched account to "<strong>{switched_to}</strong></p>
+                        <button on:click=move |_| keplr.account_switched.set(None)>"Dismiss"</button>
+                    </div>
+                    <hr />
+                </Show>
             </header>
             <main
                 // class="outline outline-1 outline-offset-8 outline-neutral-500"
             >
                 <Routes fallback=|| "This page could not be found.">
                     <Route path=path!("secret-leptos") view=|| view! { <Home /> } />
+                    <Route
+                        path=path!("secret-leptos/portfolio")
+                        view=|| view! { <Portfolio /> }
+                    />
+                    <Route path=path!("secret-leptos/send") view=|| view! { <SendTokens /> } />
+                    <Route path=path!("secret-leptos/history") view=|| view! { <History /> } />
                     <Route path=path!("secret-leptos/keplr") view=|| view! { <KeplrTests /> } />
                 </Routes>
             </main>
             <LoadingModal when=enable_keplr_action.pending() message="Requesting Connection" />
+            <ConnectDialog dialog_ref=connect_dialog_ref on_select=select_wallet />
             <OptionsMenu dialog_ref=options_dialog_ref toggle_menu=toggle_options_menu />
+            <TxToasts />
         </Router>
     }
 }
@@ -255,20 +349,105 @@ pub fn OptionsMenu(
     // let dialog_ref = NodeRef::<Dialog>::new();
     let input_element = NodeRef::<Input>::new();
 
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
     let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
     let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
 
+    let switch_network_action: Action<Network, (), SyncStorage> =
+        Action::new_unsync(move |network: &Network| {
+            let config = network.config();
+            network.save();
+            wasm_client.set_chain(&config);
+            async move {
+                // enable the new chain before switching, so the key is only refetched once
+                if keplr.enabled.get_untracked() {
+                    let wallet = keplr.wallet.get_untracked();
+                    match config.enable(wallet).await {
+                        Ok(_) => SavedConnection::new(wallet, config.chain_id).save(),
+                        Err(e) => {
+                            error!("could not enable {} in {wallet}: {e}", config.chain_id);
+                            keplr.enabled.set(false);
+                            SavedConnection::forget();
+                        }
+                    }
+                }
+                chain.set(config);
+            }
+        });
+
+    let switch_network = move |ev: leptos::ev::Event| {
+        let network = event_target_value(&ev)
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| Network::ALL.get(index).copied())
+            .unwrap_or_default();
+        if network != chain.get_untracked().network {
+            debug!("switching to {network}");
+            switch_network_action.dispatch(network);
+        }
+    };
+
+    let network_options = Network::ALL
+        .into_iter()
+        .enumerate()
+        .map(|(index, network)| {
+            view! {
+                <option value=index selected=move || chain.get().network == network>
+                    {network.to_string()}
+                </option>
+            }
+        })
+        .collect_view();
+
     let disable_keplr = move |_| {
-        keplr_sys::disable(CHAIN_ID);
+        keplr
+            .wallet
+            .get_untracked()
+            .provider()
+            .disable(chain.get_untracked().chain_id);
         keplr.enabled.set(false);
+        // don't reconnect on the next visit
+        SavedConnection::forget();
         // keplr.key.set(None);
     };
 
+    let set_endpoint_action: Action<String, Result<(), Error>, SyncStorage> =
+        Action::new_unsync(move |url: &String| {
+            let url = url.trim().to_string();
+            let chain_id = chain.get_untracked().chain_id;
+            async move {
+                let status = endpoints::validate(&url, chain_id).await?;
+                debug!("using custom endpoint {}", status.url);
+                wasm_client.set_custom(chain_id, &status.url);
+                wasm_client.endpoints.update(|endpoints| {
+                    if let Some(entry) = endpoints.iter_mut().find(|entry| entry.url == status.url)
+                    {
+                        *entry = status;
+                    }
+                });
+                Ok(())
+            }
+        });
+
+    let endpoint_error = move || {
+        set_endpoint_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| error.to_string())
+    };
+
+    let reset_endpoint = move |_| {
+        debug!("resetting the custom endpoint");
+        wasm_client.reset_custom(&chain.get_untracked());
+        spawn_local(async move { wasm_client.check_health().await });
+    };
+
     let on_submit = move |ev: leptos::ev::SubmitEvent| {
         // stop the page from reloading!
         ev.prevent_default();
 
-        debug!("updating wasm_client grpc_url");
+        debug!("validating custom grpc_url");
 
         // here, we'll extract the value from the input
         let value = input_element
@@ -281,7 +460,7 @@ pub fn OptionsMenu(
             // this means we can call`HtmlInputElement::value()`
             // to get the current value of the input
             .value();
-        wasm_client.set(Client::new(value));
+        set_endpoint_action.dispatch(value);
     };
 
     view! {
@@ -289,38 +468,56 @@ pub fn OptionsMenu(
             <button on:click=toggle_menu class="self-stretch">
                 "Close Menu"
             </button>
+            <label class="flex gap-4 self-stretch justify-between">
+                "Network"
+                <select on:change=switch_network disabled=switch_network_action.pending()>
+                    {network_options}
+                </select>
+            </label>
             <form class="flex gap-4" on:submit=on_submit>
-                <input type="text" value=GRPC_URL node_ref=input_element />
-                <input type="submit" value="Submit" class="min-w-fit" />
+                <input type="text" value=move || wasm_client.url.get() node_ref=input_element />
+                <input
+                    type="submit"
+                    value="Submit"
+                    disabled=set_endpoint_action.pending()
+                    class="min-w-fit"
+                />
             </form>
+            <Show when=move || set_endpoint_action.pending().get()>
+                <p class="text-sm">"Checking endpoint..."</p>
+            </Show>
+            <p class="text-sm text-red-500">{endpoint_error}</p>
+            <Show when=move || wasm_client.custom.get().is_some()>
+                <button on:click=reset_endpoint class="self-stretch">
+                    "Use Default Endpoints"
+                </button>
+            </Show>
             <button
                 on:click=disable_keplr
                 class="border-blue-500 text-blue-500 border-solid hover:bg-neutral-800 rounded-sm bg-[initial]"
             >
                 Disconnect Wallet
             </button>
+            <LoadingModal when=switch_network_action.pending() message="Switching Network" />
         </dialog>
     }
 }
 
+/// Token metadata only changes if the contract is migrated.
+const TOKEN_INFO_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
 #[component]
 fn Home() -> impl IntoView {
     info!("rendering <Home/>");
 
     let keplr = use_context::<KeplrSignals>().expect("keplr signals context missing!");
+    let chain = use_context::<RwSignal<ChainConfig>>().expect("chain config context missing!");
     let wasm_client = use_context::<WasmClient>().expect("wasm client context missing!");
-    let token_map = use_context::<TokenMap>().expect("tokens context missing!");
-
-    // whenever the key store changes, this will re-set 'is_keplr_enabled' to true, triggering a
-    // reload of everything subscribed to that signal
-    let keplr_keystorechange_handle =
-        window_event_listener_untyped("keplr_keystorechange", move |_| {
-            keplr.enabled.set(true);
-        });
+    let token_map = use_context::<Memo<TokenMap>>().expect("tokens context missing!");
+    let code_hashes = use_context::<CodeHashes>().expect("code hashes context missing!");
 
     on_cleanup(move || {
         info!("cleaning up <Home/>");
-        keplr_keystorechange_handle.remove()
     });
 
     // Effect::new(move |_| {
@@ -333,19 +530,16 @@ fn Home() -> impl IntoView {
     // });
 
     let viewing_keys = Resource::new(
-        move || keplr.key.track(),
-        move |_| {
-            let tokens = token_map.clone();
+        move || (keplr.key.track(), token_map.get(), chain.get().chain_id),
+        move |(_, tokens, chain_id)| {
             SendWrapper::new(async move {
                 if keplr.enabled.get_untracked() {
                     debug!("gathering viewing_keys");
                     let mut keys = Vec::new();
                     for (_, token) in tokens.iter() {
-                        let key_result =
-                            Keplr::get_secret_20_viewing_key(CHAIN_ID, &token.contract_address)
-                                .await;
+                        let key_result = keplr.viewing_key(chain_id, &token.contract_address).await;
 
-                        if let Ok(key) = key_result {
+                        if let Some(key) = key_result {
                             keys.push((
                                 token.metadata.name.clone(),
                                 token.contract_address.clone(),
@@ -382,14 +576,22 @@ fn Home() -> impl IntoView {
     };
 
     let user_balance = Resource::new(
-        move || keplr.key.get(),
-        move |key| {
+        move || (keplr.key.get(), wasm_client.refresh.track()),
+        move |(key, _)| {
             SendWrapper::new(async move {
                 if let Some(Ok(key)) = key {
-                    let bank = BankQuerier::new(wasm_client.get_untracked());
-                    match bank.balance(key.bech32_address, "uscrt").await {
+                    let denom = chain.get_untracked().denom;
+                    let balance = wasm_client
+                        .query(|client| {
+                            let address = key.bech32_address.clone();
+                            async move { BankQuerier::new(client).balance(address, denom).await }
+                        })
+                        .await;
+                    match balance {
                         Ok(balance) => {
-                            let balance: Coin = balance.balance.unwrap().into();
+                            let info = DenomInfo::from(&chain.get_untracked());
+                            let balance =
+                                Coin::from_proto(balance.balance.unwrap_or_default(), &info)?;
                             Ok(balance.to_string())
                         }
                         // TODO: do better with these Error semantics
@@ -405,33 +607,49 @@ fn Home() -> impl IntoView {
         },
     );
 
-    let encryption_utils = secretrs::EncryptionUtils::new(None, "secret-4").unwrap();
-    // TODO: revisit this. url is not needed, EncryptionUtils should be a trait
-    let options = CreateQuerierOptions {
-        url: "https://grpc.mainnet.secretsaturn.net",
-        chain_id: CHAIN_ID,
-        encryption_utils,
-    };
-
-    // TODO: move all static resources like this (query response is always the same) to a separate
-    // module. Implement caching with local storage. They can all use a random account for the
-    // EncryptionUtils, since they don't depend on user address.
+    // Static queries (the response is always the same) are cached in local storage. They can
+    // all use a random account for the EncryptionUtils, since they don't depend on user address.
     let contract_address = "secret1s09x2xvfd2lp2skgzm29w2xtena7s8fq98v852";
-    let code_hash = "9a00ca4ad505e9be7e6e6dddf8d939b7ec7e9ac8e109c8681f10db9cacb36d42";
+    let token_info_revalidated = Trigger::new();
     let token_info = Resource::new(
-        || (),
-        move |_| {
+        move || {
+            token_info_revalidated.track();
+            (wasm_client.refresh.track(), chain.get())
+        },
+        move |(_, config)| {
             debug!("loading token_info resource");
-            let compute = ComputeQuerier::new(wasm_client.get_untracked(), options.clone());
-            SendWrapper::new(async move {
-                let query = QueryMsg::TokenInfo {};
-                compute
-                    .query_secret_contract(contract_address, code_hash, query)
+            let key = CacheKey::new(config.chain_id, contract_address, &QueryMsg::TokenInfo {});
+            SendWrapper::new(cached_query(
+                key,
+                TOKEN_INFO_TTL,
+                token_info_revalidated,
+                move || async move {
+                    // nothing user specific, so a throwaway seed will do
+                    let enigma = secretrs::EncryptionUtils::new(None, config.chain_id)
+                        .map_err(|e| Error::Secret(e.to_string()))?;
+                    let code_hash = code_hashes.resolve(contract_address).await?;
+                    contract::query_contract(
+                        wasm_client,
+                        &enigma,
+                        contract_address,
+                        &code_hash,
+                        &QueryMsg::TokenInfo {},
+                    )
                     .await
-                    .map_err(Error::generic)
-            })
+                },
+            ))
         },
     );
+    let token_info_text = move || {
+        token_info.get().map(|response| {
+            response.map(|TokenInfoResponse { token_info }| {
+                format!(
+                    "{} ({}), {} decimals",
+                    token_info.name, token_info.symbol, token_info.decimals
+                )
+            })
+        })
+    };
 
     view! {
         <Show when=move || keplr.enabled.get() fallback=|| view! { <p>Nothing to see here</p> }>
@@ -459,7 +677,7 @@ fn Home() -> impl IntoView {
                     </div>
                 }
             }>
-                <p>{move || token_info.get()}</p>
+                <p>{token_info_text}</p>
             </ErrorBoundary>
             // Errors from user-specific queries should have a separate ErrorBoundary
             <ErrorBoundary fallback=|errors| {
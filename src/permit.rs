@@ -0,0 +1,172 @@
+//! SNIP-24 query permits.
+//!
+//! A permit is an amino-signed message that never gets broadcast. Contracts verify the signature
+//! themselves, so one signature can authenticate queries for every token in `allowed_tokens`.
+
+use crate::{
+    contract::query_contract,
+    enigma::Enigma,
+    error::Error,
+    keplr,
+    state::{TokenMap, WasmClient},
+};
+use rsecret::wallet::Signer;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tracing::debug;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Allowance,
+    Balance,
+    History,
+    Owner,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PermitParams {
+    pub permit_name: String,
+    pub allowed_tokens: Vec<String>,
+    pub chain_id: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PubKey {
+    #[serde(rename = "type")]
+    pub r#type: String,
+    /// Base64 encoded secp256k1 public key.
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PermitSignature {
+    pub pub_key: PubKey,
+    /// Base64 encoded signature.
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+impl Permit {
+    /// Asks the signer to sign a new permit for `allowed_tokens`.
+    pub async fn sign<S: Signer<Error = keplr::Error>>(
+        signer: &S,
+        signer_address: &str,
+        chain_id: &str,
+        permit_name: impl Into<String>,
+        allowed_tokens: Vec<String>,
+        permissions: Vec<Permission>,
+    ) -> Result<Self, Error> {
+        let params = PermitParams {
+            permit_name: permit_name.into(),
+            allowed_tokens,
+            chain_id: chain_id.to_string(),
+            permissions,
+        };
+
+        // This exact document is what the contract rebuilds to verify the signature.
+        let sign_doc = serde_json::from_value(json!({
+            "chain_id": params.chain_id,
+            "account_number": "0",
+            "sequence": "0",
+            "fee": {
+                "amount": [{ "denom": "uscrt", "amount": "0" }],
+                "gas": "1",
+            },
+            "msgs": [{
+                "type": "query_permit",
+                "value": {
+                    "permit_name": params.permit_name,
+                    "allowed_tokens": params.allowed_tokens,
+                    "permissions": params.permissions,
+                },
+            }],
+            "memo": "",
+        }))
+        .map_err(Error::generic)?;
+
+        debug!("signing permit {:?}", params.permit_name);
+        let response = signer.sign_permit(signer_address, sign_doc).await?;
+        let signature = serde_json::to_value(response.signature)
+            .and_then(serde_json::from_value)
+            .map_err(Error::generic)?;
+
+        Ok(Self { params, signature })
+    }
+
+    /// Signs a single permit covering every token in the registry.
+    pub async fn sign_for_registry<S: Signer<Error = keplr::Error>>(
+        signer: &S,
+        signer_address: &str,
+        chain_id: &str,
+        token_map: &TokenMap,
+        permissions: Vec<Permission>,
+    ) -> Result<Self, Error> {
+        let allowed_tokens = token_map
+            .values()
+            .map(|token| token.contract_address.clone())
+            .collect();
+
+        Self::sign(
+            signer,
+            signer_address,
+            chain_id,
+            "secret-leptos",
+            allowed_tokens,
+            permissions,
+        )
+        .await
+    }
+
+    pub fn allows(&self, contract_address: &str) -> bool {
+        self.params
+            .allowed_tokens
+            .iter()
+            .any(|token| token == contract_address)
+    }
+}
+
+/// The SNIP-20 queries that can be authenticated with a permit.
+///
+/// SNIP-721 contracts accept their own query types the same way; anything that serializes to the
+/// inner query works with [`query_with_permit`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Snip20QueryWithPermit {
+    Allowance { owner: String, spender: String },
+    Balance {},
+    TransferHistory { page: Option<u32>, page_size: u32 },
+    TransactionHistory { page: Option<u32>, page_size: u32 },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryMsg<'a, Q> {
+    WithPermit { permit: &'a Permit, query: Q },
+}
+
+/// Runs `query` against the contract, authenticated by `permit` instead of a viewing key.
+pub async fn query_with_permit<Q: Serialize, R: DeserializeOwned>(
+    wasm_client: WasmClient,
+    enigma: &dyn Enigma,
+    contract_address: &str,
+    code_hash: &str,
+    permit: &Permit,
+    query: Q,
+) -> Result<R, Error> {
+    if !permit.allows(contract_address) {
+        return Err(Error::generic(format!(
+            "permit {:?} does not cover {contract_address}",
+            permit.params.permit_name
+        )));
+    }
+
+    let query = QueryMsg::WithPermit { permit, query };
+    query_contract(wasm_client, enigma, contract_address, code_hash, &query).await
+}
@@ -1,3 +1,3 @@
-pub use crate::constants::*;
+pub use crate::config::*;
 pub use crate::error::Error;
 pub use crate::utils::*;
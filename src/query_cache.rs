@@ -0,0 +1,105 @@
+//! A localStorage cache for contract queries whose answers rarely change, like `token_info`, and
+//! for per-account data like transaction history.
+//!
+//! Entries are keyed by chain id, contract (or account) address and query. An entry older than
+//! its TTL is still returned, but refetched in the background (stale-while-revalidate). Once the
+//! fresh value is stored, the [`Trigger`] passed to [`cached_query`] is notified, so a `Resource`
+//! that tracks it reruns and picks the new value up from the cache.
+
+use crate::{error::Error, storage};
+use leptos::{prelude::*, task::spawn_local};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashSet, future::Future, time::Duration};
+use tracing::{debug, warn};
+use web_sys::js_sys::Date;
+
+thread_local! {
+    /// Keys being revalidated right now, so concurrent readers don't refetch them again.
+    static REVALIDATING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    pub fn new(chain_id: &str, contract_address: &str, query: &impl Serialize) -> Self {
+        let query = serde_json::to_string(query).unwrap_or_default();
+        Self(format!(
+            "secret-leptos:query:{chain_id}:{contract_address}:{query}"
+        ))
+    }
+
+    /// For data about an account rather than a contract. `what` tells entries apart.
+    pub fn account(chain_id: &str, address: &str, what: &str) -> Self {
+        Self(format!("secret-leptos:account:{chain_id}:{address}:{what}"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<T> {
+    value: T,
+    /// Milliseconds since the epoch.
+    fetched_at: f64,
+}
+
+impl<T> CacheEntry<T> {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        Date::now() - self.fetched_at < ttl.as_millis() as f64
+    }
+}
+
+fn store<T: Serialize>(key: &CacheKey, value: &T) {
+    let entry = CacheEntry {
+        value,
+        fetched_at: Date::now(),
+    };
+    storage::save(&key.0, &entry);
+}
+
+/// Drops the cached value, so the next read fetches it.
+pub fn invalidate(key: &CacheKey) {
+    storage::remove(&key.0)
+}
+
+/// Returns the cached value for `key`, calling `fetch` if there is none.
+///
+/// A value older than `ttl` is returned as is while `fetch` runs in the background; when it
+/// succeeds, `revalidated` is notified.
+pub async fn cached_query<T, F, Fut>(
+    key: CacheKey,
+    ttl: Duration,
+    revalidated: Trigger,
+    fetch: F,
+) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned + 'static,
+    F: FnOnce() -> Fut + 'static,
+    Fut: Future<Output = Result<T, Error>> + 'static,
+{
+    match storage::load::<CacheEntry<T>>(&key.0) {
+        Some(entry) if entry.is_fresh(ttl) => Ok(entry.value),
+        Some(entry) => {
+            let newly_revalidating =
+                REVALIDATING.with_borrow_mut(|keys| keys.insert(key.0.clone()));
+            if newly_revalidating {
+                debug!("revalidating {}", key.0);
+                spawn_local(async move {
+                    match fetch().await {
+                        Ok(value) => {
+                            store(&key, &value);
+                            revalidated.notify();
+                        }
+                        Err(error) => warn!("failed to revalidate {}: {error}", key.0),
+                    }
+                    REVALIDATING.with_borrow_mut(|keys| keys.remove(&key.0));
+                });
+            }
+            Ok(entry.value)
+        }
+        None => {
+            let value = fetch().await?;
+            store(&key, &value);
+            Ok(value)
+        }
+    }
+}
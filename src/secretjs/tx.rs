@@ -3,7 +3,7 @@ use wasm_bindgen::prelude::*;
 
 pub fn create_tx_options(
     gas_limit: Option<u32>,
-    gas_price_in_fee_denom: Option<u32>,
+    gas_price_in_fee_denom: Option<f64>,
     fee_denom: Option<&str>,
     fee_granter: Option<&str>,
     memo: Option<&str>,
@@ -22,7 +22,7 @@ pub fn create_tx_options(
         let _ = js_sys::Reflect::set(
             &tx_options,
             &JsValue::from_str("gasPriceInFeeDenom"),
-            &JsValue::from_f64(gas_price_in_fee_denom as f64),
+            &JsValue::from_f64(gas_price_in_fee_denom),
         );
     }
 
@@ -55,7 +55,7 @@ pub fn create_tx_options(
 
 pub struct TxOptionsBuilder {
     gas_limit: Option<u32>,
-    gas_price_in_fee_denom: Option<u32>,
+    gas_price_in_fee_denom: Option<f64>,
     fee_denom: Option<String>,
     fee_granter: Option<String>,
     memo: Option<String>,
@@ -77,7 +77,7 @@ impl TxOptionsBuilder {
         self
     }
 
-    pub fn gas_price_in_fee_denom(mut self, gas_price_in_fee_denom: u32) -> Self {
+    pub fn gas_price_in_fee_denom(mut self, gas_price_in_fee_denom: f64) -> Self {
         self.gas_price_in_fee_denom = Some(gas_price_in_fee_denom);
         self
     }
@@ -112,7 +112,7 @@ impl TxOptionsBuilder {
             let _ = js_sys::Reflect::set(
                 &tx_options,
                 &JsValue::from_str("gasPriceInFeeDenom"),
-                &JsValue::from_f64(gas_price_in_fee_denom as f64),
+                &JsValue::from_f64(gas_price_in_fee_denom),
             );
         }
 
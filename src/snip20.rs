@@ -0,0 +1,184 @@
+//! SNIP-20 `transfer` and `send` messages.
+
+use crate::{
+    coin::Coin,
+    error::Error,
+    keplr::tokens::ContractInfo,
+    tx::{EncodedMsg, ExecuteMsg, SigningClient},
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Snip20ExecuteMsg {
+    Transfer {
+        recipient: String,
+        /// `Uint128`, which serializes as a string.
+        amount: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+    },
+    Send {
+        recipient: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        recipient_code_hash: Option<String>,
+        amount: String,
+        /// Base64 encoded, passed on to the recipient's `receive` handler.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        msg: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+    },
+}
+
+/// A callback for the contract receiving a [`send_msg`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendCallback {
+    pub code_hash: String,
+    /// The JSON message for the recipient's `receive` handler.
+    pub msg: serde_json::Value,
+}
+
+/// Parses user input into an amount of `token`, using its decimals.
+pub fn parse_amount(input: &str, token: &ContractInfo) -> Result<Coin, Error> {
+    let coin = Coin::parse(input, &token.into())?;
+    match coin.amount.is_zero() {
+        true => Err(Error::generic("amount must be more than zero")),
+        false => Ok(coin),
+    }
+}
+
+fn execute(
+    token: &ContractInfo,
+    code_hash: &str,
+    msg: Snip20ExecuteMsg,
+) -> ExecuteMsg<Snip20ExecuteMsg> {
+    ExecuteMsg {
+        contract_address: token.contract_address.clone(),
+        code_hash: code_hash.to_string(),
+        msg,
+        sent_funds: vec![],
+    }
+}
+
+fn non_empty(memo: &str) -> Option<String> {
+    Some(memo.trim().to_string()).filter(|memo| !memo.is_empty())
+}
+
+/// Moves `amount` of `token` to `recipient`.
+pub async fn transfer_msg(
+    client: &SigningClient,
+    token: &ContractInfo,
+    code_hash: &str,
+    recipient: &str,
+    amount: &Coin,
+    memo: &str,
+) -> Result<EncodedMsg, Error> {
+    let msg = transfer(recipient, amount, memo);
+    client.execute_msg(&execute(token, code_hash, msg)).await
+}
+
+fn transfer(recipient: &str, amount: &Coin, memo: &str) -> Snip20ExecuteMsg {
+    Snip20ExecuteMsg::Transfer {
+        recipient: recipient.to_string(),
+        amount: amount.amount.raw().to_string(),
+        memo: non_empty(memo),
+    }
+}
+
+/// Like [`transfer_msg`], but a recipient contract is notified through `callback`.
+pub async fn send_msg(
+    client: &SigningClient,
+    token: &ContractInfo,
+    code_hash: &str,
+    recipient: &str,
+    amount: &Coin,
+    memo: &str,
+    callback: Option<SendCallback>,
+) -> Result<EncodedMsg, Error> {
+    let msg = send(recipient, amount, memo, callback)?;
+    client.execute_msg(&execute(token, code_hash, msg)).await
+}
+
+fn send(
+    recipient: &str,
+    amount: &Coin,
+    memo: &str,
+    callback: Option<SendCallback>,
+) -> Result<Snip20ExecuteMsg, Error> {
+    let (recipient_code_hash, msg) = match callback {
+        Some(callback) => {
+            let msg = serde_json::to_vec(&callback.msg).map_err(Error::generic)?;
+            (Some(callback.code_hash), Some(BASE64_STANDARD.encode(msg)))
+        }
+        None => (None, None),
+    };
+    Ok(Snip20ExecuteMsg::Send {
+        recipient: recipient.to_string(),
+        recipient_code_hash,
+        amount: amount.amount.raw().to_string(),
+        msg,
+        memo: non_empty(memo),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coin::{Amount, DenomInfo};
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    const RECIPIENT: &str = "secret1fc3fzy78ttp0lwuujw7e52rhspxn8uj52zfyne";
+
+    fn sscrt(amount: u128) -> Coin {
+        let info = DenomInfo {
+            denom: "secret1k0jntykt7e4g3y88ltc60czgjuqdy4c9e8fzek".to_string(),
+            symbol: "sSCRT".to_string(),
+            decimals: 6,
+        };
+        Coin::new(&info, Amount::new(amount, info.decimals))
+    }
+
+    fn wire(msg: &Snip20ExecuteMsg) -> serde_json::Value {
+        serde_json::to_value(msg).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn transfer_json() {
+        assert_eq!(
+            wire(&transfer(RECIPIENT, &sscrt(1_500_000), " ")),
+            json!({ "transfer": { "recipient": RECIPIENT, "amount": "1500000" } })
+        );
+        assert_eq!(
+            wire(&transfer(RECIPIENT, &sscrt(1), "rent")),
+            json!({ "transfer": { "recipient": RECIPIENT, "amount": "1", "memo": "rent" } })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn send_json() {
+        assert_eq!(
+            wire(&send(RECIPIENT, &sscrt(42), "", None).unwrap()),
+            json!({ "send": { "recipient": RECIPIENT, "amount": "42" } })
+        );
+
+        let callback = SendCallback {
+            code_hash: "abc123".to_string(),
+            msg: json!({ "deposit": {} }),
+        };
+        assert_eq!(
+            wire(&send(RECIPIENT, &sscrt(42), "", Some(callback)).unwrap()),
+            json!({
+                "send": {
+                    "recipient": RECIPIENT,
+                    "recipient_code_hash": "abc123",
+                    "amount": "42",
+                    // {"deposit":{}}
+                    "msg": "eyJkZXBvc2l0Ijp7fX0=",
+                }
+            })
+        );
+    }
+}
@@ -1,26 +1,210 @@
 use crate::{
-    constants::*,
+    config::{ChainConfig, Network},
+    endpoints::{self, EndpointStatus},
     error::Error,
-    keplr::{tokens::ContractInfo, Keplr, Key},
+    keplr::{tokens::ContractInfo, Key, Wallet, WalletProvider},
+    permit::{Permission, Permit},
+    storage,
 };
 use leptos::prelude::*;
 use send_wrapper::SendWrapper;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, future::Future};
 use tonic_web_wasm_client::Client;
-use tracing::debug;
+use tracing::{debug, warn};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// A gRPC-web client backed by a pool of endpoints for the same chain.
+///
+/// `client` and `url` always point at the active endpoint. [`WasmClient::check_health`] probes
+/// the whole pool and switches to the healthiest endpoint; [`WasmClient::query`] retries on the
+/// next one when the active endpoint can't be reached.
+#[derive(Copy, Clone)]
 pub struct WasmClient {
     pub client: RwSignal<Client>,
     pub url: RwSignal<String>,
+    /// One status per endpoint in the pool, from the latest health check.
+    pub endpoints: RwSignal<Vec<EndpointStatus>>,
+    /// An endpoint the user entered. It's preferred over the others for as long as it's healthy.
+    pub custom: RwSignal<Option<String>>,
+    /// Notified when the user changes endpoints. Resources that query the chain should track it.
+    pub refresh: Trigger,
 }
 
 impl WasmClient {
-    pub fn new() -> Self {
-        Self {
-            client: RwSignal::new(Client::new(GRPC_URL.to_string())),
-            url: RwSignal::new(GRPC_URL.to_string()),
+    pub fn new(chain: &ChainConfig) -> Self {
+        let wasm_client = Self {
+            client: RwSignal::new(Client::new(String::new())),
+            url: RwSignal::new(String::new()),
+            endpoints: RwSignal::new(vec![]),
+            custom: RwSignal::new(None),
+            refresh: Trigger::new(),
+        };
+        wasm_client.set_chain(chain);
+
+        wasm_client
+    }
+
+    /// Replaces the pool with the endpoints for `chain`, plus the custom endpoint saved for it.
+    pub fn set_chain(&self, chain: &ChainConfig) {
+        let custom = endpoints::load_custom(chain.chain_id);
+        let urls = custom
+            .iter()
+            .map(String::as_str)
+            .chain(chain.grpc_urls.iter().copied())
+            .collect::<Vec<_>>();
+
+        self.custom.set(custom.clone());
+        self.set_endpoints(&urls);
+    }
+
+    /// Switches to an endpoint the user entered, and remembers it for `chain_id`. Check it with
+    /// [`endpoints::validate`] first.
+    pub fn set_custom(&self, chain_id: &str, url: &str) {
+        endpoints::save_custom(chain_id, url);
+        self.custom.set(Some(url.to_string()));
+        self.endpoints.update(|endpoints| {
+            if !endpoints.iter().any(|status| status.url == url) {
+                endpoints.insert(0, EndpointStatus::unknown(url));
+            }
+        });
+        self.set_url(url);
+        self.refresh.notify();
+    }
+
+    /// Forgets the custom endpoint and goes back to the healthiest preset.
+    pub fn reset_custom(&self, chain: &ChainConfig) {
+        endpoints::forget_custom(chain.chain_id);
+        self.set_chain(chain);
+        self.refresh.notify();
+    }
+
+    /// Points the client at another gRPC-web endpoint.
+    pub fn set_url(&self, url: &str) {
+        self.client.set(Client::new(url.to_string()));
+        self.url.set(url.to_string());
+    }
+
+    /// Replaces the pool, for example after switching networks.
+    pub fn set_endpoints(&self, urls: &[&str]) {
+        self.endpoints
+            .set(urls.iter().copied().map(EndpointStatus::unknown).collect());
+        if let Some(url) = urls.first() {
+            self.set_url(url);
+        }
+    }
+
+    /// The latest health check result for the active endpoint.
+    pub fn active(&self) -> Option<EndpointStatus> {
+        let url = self.url.get();
+        self.endpoints
+            .with(|endpoints| endpoints.iter().find(|status| status.url == url).cloned())
+    }
+
+    /// Probes every endpoint in the pool and switches to the healthiest one.
+    pub async fn check_health(&self) {
+        let urls: Vec<String> = self.endpoints.with_untracked(|endpoints| {
+            endpoints.iter().map(|status| status.url.clone()).collect()
+        });
+        let statuses = futures::future::join_all(urls.iter().cloned().map(endpoints::probe)).await;
+
+        // the pool was replaced while probing
+        let unchanged = self
+            .endpoints
+            .with_untracked(|endpoints| endpoints.iter().map(|status| &status.url).eq(urls.iter()));
+        if !unchanged {
+            return;
         }
+
+        let custom = self.custom.get_untracked();
+        let preferred = statuses
+            .iter()
+            .find(|status| Some(&status.url) == custom.as_ref() && status.is_healthy())
+            .or_else(|| endpoints::healthiest(&statuses));
+
+        match preferred {
+            Some(best) if best.url != self.url.get_untracked() => {
+                debug!("switching to the healthiest endpoint: {}", best.url);
+                self.set_url(&best.url);
+            }
+            Some(_) => {}
+            None => warn!("no healthy gRPC endpoints"),
+        }
+        self.endpoints.set(statuses);
+    }
+
+    /// Marks the active endpoint as down and moves to the next healthy one, or simply the next
+    /// one in the pool if none are known to be healthy. Returns `false` if there is nowhere to go.
+    pub fn fail_over(&self) -> bool {
+        let failed = self.url.get_untracked();
+        self.endpoints.update(|endpoints| {
+            if let Some(status) = endpoints.iter_mut().find(|status| status.url == failed) {
+                *status = EndpointStatus::unknown(failed.clone());
+            }
+        });
+
+        let next = self.endpoints.with_untracked(|endpoints| {
+            endpoints::healthiest(endpoints)
+                .map(|status| status.url.clone())
+                .or_else(|| {
+                    let position = endpoints.iter().position(|status| status.url == failed);
+                    let next = position.map_or(0, |position| position + 1) % endpoints.len().max(1);
+                    endpoints.get(next).map(|status| status.url.clone())
+                })
+                .filter(|url| *url != failed)
+        });
+
+        match next {
+            Some(url) => {
+                warn!("{failed} is unreachable, failing over to {url}");
+                self.set_url(&url);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs `query` against the active endpoint, failing over to the others on transport errors.
+    pub async fn query<T, E, F, Fut>(&self, query: F) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.with_failover(query, endpoints::is_transport_error)
+            .await
+    }
+
+    /// Like [`Self::query`], but only fails over when the request never reached the node, so
+    /// `request` runs at most once on any node. For requests with side effects, like broadcasts.
+    pub async fn send<T, E, F, Fut>(&self, request: F) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.with_failover(request, endpoints::is_connect_error)
+            .await
+    }
+
+    async fn with_failover<T, E, F, Fut>(&self, request: F, retry: fn(&E) -> bool) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let attempts = self.endpoints.with_untracked(Vec::len).max(1);
+        let mut result = request(self.client.get_untracked()).await;
+
+        for _ in 1..attempts {
+            match &result {
+                Err(error) if retry(error) && self.fail_over() => {
+                    result = request(self.client.get_untracked()).await;
+                }
+                _ => break,
+            }
+        }
+
+        result
     }
 }
 
@@ -32,14 +216,21 @@ impl std::ops::Deref for WasmClient {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TokenMap(HashMap<String, ContractInfo>);
 
 impl TokenMap {
-    pub fn new() -> Self {
+    pub fn new(chain: &ChainConfig) -> Self {
+        // the Keplr contract registry only lists mainnet tokens
+        if chain.network != Network::Mainnet {
+            debug!("No token registry for {}", chain.chain_id);
+            return Self(HashMap::new());
+        }
+
         let json = include_str!(concat!(env!("OUT_DIR"), "/token_map.json"));
         let token_map: HashMap<String, ContractInfo> =
             serde_json::from_str(json).expect("Failed to deserialize token_map");
+        debug!("Loaded {} tokens", token_map.len());
 
         Self(token_map)
     }
@@ -59,27 +250,250 @@ impl AsRef<HashMap<String, ContractInfo>> for TokenMap {
     }
 }
 
+/// `(chain_id, address)`. The same address exists on every chain with the same bech32 prefix.
+type AccountKey = (String, String);
+
+fn account_key(chain_id: &str, address: &str) -> AccountKey {
+    (chain_id.to_string(), address.to_string())
+}
+
+/// Data that belongs to a single account on a single chain.
+#[derive(Copy, Clone)]
+pub struct AccountCache {
+    /// contract address -> viewing key
+    pub viewing_keys: RwSignal<HashMap<AccountKey, HashMap<String, String>>>,
+    pub permits: RwSignal<HashMap<AccountKey, Permit>>,
+}
+
+impl AccountCache {
+    pub fn new() -> Self {
+        Self {
+            viewing_keys: RwSignal::new(HashMap::new()),
+            permits: RwSignal::new(HashMap::new()),
+        }
+    }
+
+    pub fn viewing_key(
+        &self,
+        chain_id: &str,
+        address: &str,
+        contract_address: &str,
+    ) -> Option<String> {
+        self.viewing_keys.with_untracked(|keys| {
+            keys.get(&account_key(chain_id, address))
+                .and_then(|keys| keys.get(contract_address))
+                .cloned()
+        })
+    }
+
+    pub fn set_viewing_key(
+        &self,
+        chain_id: &str,
+        address: &str,
+        contract_address: &str,
+        viewing_key: String,
+    ) {
+        self.viewing_keys.update(|keys| {
+            keys.entry(account_key(chain_id, address))
+                .or_default()
+                .insert(contract_address.to_string(), viewing_key);
+        })
+    }
+
+    /// The permit `address` signed on `chain_id`, if it covers `contract_address`.
+    pub fn permit(&self, chain_id: &str, address: &str, contract_address: &str) -> Option<Permit> {
+        self.permits.with_untracked(|permits| {
+            permits
+                .get(&account_key(chain_id, address))
+                .filter(|permit| permit.allows(contract_address))
+                .cloned()
+        })
+    }
+
+    /// Keeps `permit` for the chain it was signed for.
+    pub fn set_permit(&self, address: &str, permit: Permit) {
+        self.permits.update(|permits| {
+            permits.insert(account_key(&permit.params.chain_id, address), permit);
+        })
+    }
+
+    /// Drops everything cached for `address`, on every chain.
+    pub fn invalidate(&self, address: &str) {
+        debug!("invalidating cached data for {address}");
+        self.viewing_keys.update(|keys| {
+            keys.retain(|(_, cached), _| cached != address);
+        });
+        self.permits.update(|permits| {
+            permits.retain(|(_, cached), _| cached != address);
+        });
+    }
+}
+
+/// The wallet and chain the user last connected with, so the next visit can reconnect.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedConnection {
+    pub wallet: Wallet,
+    pub chain_id: String,
+}
+
+impl SavedConnection {
+    const STORAGE_KEY: &'static str = "secret-leptos:connection";
+
+    pub fn new(wallet: Wallet, chain_id: impl Into<String>) -> Self {
+        Self {
+            wallet,
+            chain_id: chain_id.into(),
+        }
+    }
+
+    pub fn load() -> Option<Self> {
+        storage::load(Self::STORAGE_KEY)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::STORAGE_KEY, self)
+    }
+
+    pub fn forget() {
+        storage::remove(Self::STORAGE_KEY)
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct KeplrSignals {
     pub enabled: RwSignal<bool>,
+    /// The extension the user connected with.
+    pub wallet: RwSignal<Wallet>,
     pub key: LocalResource<Result<Key, Error>>,
     // pub key: RwSignal<Option<Key>>,
+    pub cache: AccountCache,
+    /// Set to the new address whenever the user switches accounts in the extension.
+    pub account_switched: RwSignal<Option<String>>,
 }
 
 impl KeplrSignals {
-    pub fn new() -> Self {
+    /// Also listens for key store changes, so this must be called inside the root component.
+    pub fn new(chain: RwSignal<ChainConfig>) -> Self {
         let enabled = RwSignal::new(false);
+        let wallet = RwSignal::new(Wallet::default());
+        let keystore_changed = Trigger::new();
+        let cache = AccountCache::new();
+        let account_switched = RwSignal::new(None);
+
+        let handles = Wallet::ALL.map(|listen_to| {
+            window_event_listener_untyped(listen_to.provider().keystore_change_event(), move |_| {
+                if enabled.get_untracked() && wallet.get_untracked() == listen_to {
+                    debug!("key store in {listen_to} changed, refetching the key");
+                    keystore_changed.notify();
+                }
+            })
+        });
+        Owner::on_cleanup(move || handles.into_iter().for_each(|handle| handle.remove()));
+
         let key = LocalResource::new(move || {
+            keystore_changed.track();
+            let chain_id = chain.get().chain_id;
             SendWrapper::new(async move {
                 if enabled.get() {
-                    debug!("keplr is enabled! getting key");
-                    Keplr::get_key(CHAIN_ID).await.map_err(Into::into)
+                    let wallet = wallet.get();
+                    debug!("{wallet} is enabled! getting key");
+                    wallet
+                        .provider()
+                        .get_key(chain_id)
+                        .await
+                        .map_err(Into::into)
                 } else {
                     Err(Error::KeplrDisabled)
                 }
             })
         });
 
-        Self { enabled, key }
+        // Compares each new key with the previous one, to catch account switches
+        Effect::new(move |previous: Option<Option<String>>| {
+            let previous = previous.flatten();
+            let current = match key.get() {
+                // still loading
+                None => return previous,
+                Some(key) => key.ok().map(|key| key.bech32_address),
+            };
+
+            if let Some(previous) = previous.as_ref() {
+                if current.as_ref() != Some(previous) {
+                    cache.invalidate(previous);
+                    if current.is_some() {
+                        account_switched.set(current.clone());
+                    }
+                }
+            }
+
+            current
+        });
+
+        Self {
+            enabled,
+            wallet,
+            key,
+            cache,
+            account_switched,
+        }
+    }
+
+    /// Address of the connected account, without subscribing to changes.
+    pub fn address_untracked(&self) -> Option<String> {
+        self.key
+            .get_untracked()
+            .and_then(Result::ok)
+            .map(|key| key.bech32_address)
+    }
+
+    /// The viewing key for a SNIP-20 contract, from the cache or else the wallet.
+    pub async fn viewing_key(&self, chain_id: &str, contract_address: &str) -> Option<String> {
+        let address = self.address_untracked()?;
+        if let Some(key) = self.cache.viewing_key(chain_id, &address, contract_address) {
+            return Some(key);
+        }
+
+        let key = self
+            .wallet
+            .get_untracked()
+            .provider()
+            .get_secret_20_viewing_key(chain_id, contract_address)
+            .await
+            .ok()?;
+        self.cache
+            .set_viewing_key(chain_id, &address, contract_address, key.clone());
+
+        Some(key)
+    }
+
+    /// A permit the connected account signed on `chain_id` during this session, if it covers
+    /// `contract_address`.
+    pub fn permit(&self, chain_id: &str, contract_address: &str) -> Option<Permit> {
+        let address = self.address_untracked()?;
+        self.cache.permit(chain_id, &address, contract_address)
+    }
+
+    /// Asks the wallet to sign one balance permit for every token in `tokens`, and keeps it for
+    /// the rest of the session. Balances of those tokens no longer need viewing keys.
+    pub async fn sign_permit(&self, chain_id: &str, tokens: &TokenMap) -> Result<Permit, Error> {
+        let address = self.address_untracked().ok_or(Error::KeplrDisabled)?;
+        let signer = self
+            .wallet
+            .get_untracked()
+            .provider()
+            .get_offline_signer_auto(chain_id)
+            .await?;
+
+        let permit = Permit::sign_for_registry(
+            &signer,
+            &address,
+            chain_id,
+            tokens,
+            vec![Permission::Balance],
+        )
+        .await?;
+        self.cache.set_permit(&address, permit.clone());
+
+        Ok(permit)
     }
 }
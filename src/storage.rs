@@ -0,0 +1,40 @@
+//! Typed access to `window.localStorage`.
+//!
+//! Values are stored as JSON. Storage can be unavailable (private browsing, disabled cookies) or
+//! hold data from an older build, so every failure is logged and treated as a missing value.
+
+use leptos::prelude::window;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+use web_sys::Storage;
+
+fn local_storage() -> Option<Storage> {
+    window().local_storage().ok().flatten()
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = local_storage()?.get_item(key).ok().flatten()?;
+    serde_json::from_str(&value)
+        .inspect_err(|error| warn!("ignoring stored {key:?}: {error}"))
+        .ok()
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let Some(storage) = local_storage() else {
+        return warn!("localStorage is unavailable, {key:?} will not be saved");
+    };
+    match serde_json::to_string(value) {
+        Ok(value) => {
+            if storage.set_item(key, &value).is_err() {
+                warn!("failed to save {key:?}");
+            }
+        }
+        Err(error) => warn!("failed to serialize {key:?}: {error}"),
+    }
+}
+
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
@@ -0,0 +1,573 @@
+//! Building, signing and broadcasting transactions with the connected wallet.
+//!
+//! Transactions are signed in direct (protobuf) mode, which every software wallet supports.
+//! Hardware wallets (Ledger, Keystone) can only sign amino JSON, so for them every message also
+//! carries its amino form and the transaction is signed in `SIGN_MODE_LEGACY_AMINO_JSON`.
+
+use crate::{
+    coin::Coin,
+    config::ChainConfig,
+    enigma::{self, Enigma, NONCE_LEN},
+    error::Error,
+    fee, history,
+    keplr::{self, KeplrEnigmaUtils, KeplrSigner, Key, WalletProvider},
+    state::{KeplrSignals, WasmClient},
+    tx_tracker::TxTracker,
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use rsecret::wallet::{SignDocVariant, Signer, StdSignDoc};
+use secretrs::{
+    bank::MsgSend,
+    compute::MsgExecuteContract,
+    crypto::PublicKey,
+    proto::{
+        cosmos::{
+            auth::v1beta1::{
+                query_client::QueryClient as AuthQueryClient, BaseAccount, QueryAccountRequest,
+            },
+            base::abci::v1beta1::{TxMsgData, TxResponse},
+            tx::v1beta1::{
+                service_client::ServiceClient, BroadcastMode, BroadcastTxRequest, GetTxRequest,
+                SimulateRequest, TxRaw,
+            },
+        },
+        secret::compute::v1beta1::MsgExecuteContractResponse,
+        traits::Message,
+    },
+    tx::{Body, Fee, ModeInfo, Msg, SignDoc, SignMode, SignerInfo},
+    AccountId, Any,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tonic_web_wasm_client::Client;
+use tracing::debug;
+
+/// Usually made from a [`FeeOption`](crate::fee::FeeOption).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxOptions {
+    pub gas_limit: u64,
+    /// Price per unit of gas, in `fee_denom`. Can be fractional.
+    pub gas_price: f64,
+    /// `None` means the chain's base denom.
+    pub fee_denom: Option<String>,
+    pub memo: String,
+}
+
+impl Default for TxOptions {
+    fn default() -> Self {
+        Self {
+            gas_limit: 200_000,
+            gas_price: 0.25,
+            fee_denom: None,
+            memo: String::new(),
+        }
+    }
+}
+
+/// A contract execution, before encryption.
+#[derive(Debug, Clone)]
+pub struct ExecuteMsg<T> {
+    pub contract_address: String,
+    pub code_hash: String,
+    pub msg: T,
+    pub sent_funds: Vec<Coin>,
+}
+
+/// A message ready to go into a transaction. Contract executions also keep the nonce they were
+/// encrypted with, which is needed to decrypt their responses.
+#[derive(Debug, Clone)]
+pub struct EncodedMsg {
+    pub any: Any,
+    pub nonce: Option<[u8; NONCE_LEN]>,
+    /// The `{ type, value }` amino JSON form, for wallets that can only sign amino. `None` if
+    /// the message has no amino form here.
+    pub amino: Option<Value>,
+}
+
+impl From<Any> for EncodedMsg {
+    fn from(any: Any) -> Self {
+        Self {
+            any,
+            nonce: None,
+            amino: None,
+        }
+    }
+}
+
+/// Signs with the connected wallet and broadcasts to the active endpoint.
+///
+/// Contract messages are encrypted with the wallet's seed, so the wallet can decrypt them (and
+/// their responses) when it shows the transaction, and so can we.
+pub struct SigningClient {
+    chain: ChainConfig,
+    wasm_client: WasmClient,
+    signer: KeplrSigner,
+    key: Key,
+    enigma: KeplrEnigmaUtils,
+}
+
+impl SigningClient {
+    pub async fn new(
+        keplr: KeplrSignals,
+        wasm_client: WasmClient,
+        chain: ChainConfig,
+    ) -> Result<Self, Error> {
+        if !keplr.enabled.get_untracked() {
+            return Err(Error::KeplrDisabled);
+        }
+        let provider = keplr.wallet.get_untracked().provider();
+        let key = provider.get_key(chain.chain_id).await?;
+        let signer = provider.get_offline_signer_auto(chain.chain_id).await?;
+        let enigma = provider.get_enigma_utils(chain.chain_id)?.into();
+
+        Ok(Self {
+            chain,
+            wasm_client,
+            signer,
+            key,
+            enigma,
+        })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.key.bech32_address
+    }
+
+    pub fn chain(&self) -> &ChainConfig {
+        &self.chain
+    }
+
+    pub fn enigma(&self) -> &KeplrEnigmaUtils {
+        &self.enigma
+    }
+
+    fn account_id(address: &str) -> Result<AccountId, Error> {
+        address.parse().map_err(Error::generic)
+    }
+
+    /// Encrypts `msg` for the contract, the same way the chain's enclave expects it.
+    pub async fn execute_msg<T: Serialize>(
+        &self,
+        msg: &ExecuteMsg<T>,
+    ) -> Result<EncodedMsg, Error> {
+        let json = serde_json::to_value(&msg.msg).map_err(Error::generic)?;
+        let encrypted = self.enigma.encrypt(&msg.code_hash, &json).await?;
+        let nonce = enigma::nonce(&encrypted)?;
+
+        let amino = json!({
+            "type": "wasm/MsgExecuteContract",
+            "value": {
+                "sender": self.address(),
+                "contract": msg.contract_address,
+                "msg": BASE64_STANDARD.encode(&encrypted),
+                "sent_funds": amino_coins(&msg.sent_funds),
+            },
+        });
+        let sent_funds = to_secretrs_coins(&msg.sent_funds)?;
+
+        let any = MsgExecuteContract {
+            sender: Self::account_id(self.address())?,
+            contract: Self::account_id(&msg.contract_address)?,
+            msg: encrypted,
+            sent_funds,
+        }
+        .to_any()
+        .map_err(Error::generic)?;
+
+        Ok(EncodedMsg {
+            any,
+            nonce: Some(nonce),
+            amino: Some(amino),
+        })
+    }
+
+    async fn account(&self) -> Result<BaseAccount, Error> {
+        let address = self.address().to_string();
+        let response = self
+            .wasm_client
+            .query(|client| {
+                let address = address.clone();
+                async move {
+                    AuthQueryClient::new(client)
+                        .account(QueryAccountRequest { address })
+                        .await
+                }
+            })
+            .await
+            .map_err(|status| Error::Secret(status.message().to_string()))?;
+
+        let account = response
+            .into_inner()
+            .account
+            .ok_or_else(|| Error::generic("account not found, it needs to receive funds first"))?;
+        BaseAccount::decode(account.value.as_slice()).map_err(Error::generic)
+    }
+
+    /// A bank transfer of `amount` from the connected account to `to_address`.
+    pub fn send_msg(&self, to_address: &str, amount: &[Coin]) -> Result<EncodedMsg, Error> {
+        let any = MsgSend {
+            from_address: Self::account_id(self.address())?,
+            to_address: Self::account_id(to_address)?,
+            amount: to_secretrs_coins(amount)?,
+        }
+        .to_any()
+        .map_err(Error::generic)?;
+
+        Ok(EncodedMsg {
+            any,
+            nonce: None,
+            amino: Some(json!({
+                "type": "cosmos-sdk/MsgSend",
+                "value": {
+                    "from_address": self.address(),
+                    "to_address": to_address,
+                    "amount": amino_coins(amount),
+                },
+            })),
+        })
+    }
+
+    fn public_key(&self) -> Result<PublicKey, Error> {
+        PublicKey::from_raw_secp256k1(&self.key.pub_key)
+            .ok_or_else(|| Error::generic("invalid public key"))
+    }
+
+    fn fee(&self, options: &TxOptions) -> Result<(String, u128), Error> {
+        let fee_denom = options.fee_denom.as_deref().unwrap_or(self.chain.denom);
+        Ok((
+            fee_denom.to_string(),
+            fee::fee_amount(options.gas_limit, options.gas_price),
+        ))
+    }
+
+    fn sign_doc(
+        &self,
+        account: &BaseAccount,
+        msgs: &[EncodedMsg],
+        options: &TxOptions,
+    ) -> Result<SignDoc, Error> {
+        let body = Body::new(
+            msgs.iter().map(|msg| msg.any.clone()),
+            options.memo.clone(),
+            0u32,
+        );
+        let (fee_denom, fee_amount) = self.fee(options)?;
+        let fee = Fee::from_amount_and_gas(
+            secretrs::Coin {
+                denom: fee_denom.parse().map_err(Error::generic)?,
+                amount: fee_amount,
+            },
+            options.gas_limit,
+        );
+        let auth_info =
+            SignerInfo::single_direct(Some(self.public_key()?), account.sequence).auth_info(fee);
+
+        Ok(SignDoc {
+            body_bytes: body.into_bytes().map_err(Error::generic)?,
+            auth_info_bytes: auth_info.into_bytes().map_err(Error::generic)?,
+            chain_id: self.chain.chain_id.to_string(),
+            account_number: account.account_number,
+        })
+    }
+
+    /// Runs the transaction without committing it and returns the gas it used.
+    pub async fn simulate(&self, msgs: &[EncodedMsg], memo: &str) -> Result<u64, Error> {
+        let account = self.account().await?;
+        let options = TxOptions {
+            memo: memo.to_string(),
+            ..Default::default()
+        };
+        let sign_doc = self.sign_doc(&account, msgs, &options)?;
+
+        // signatures aren't verified in simulation, but there has to be one per signer
+        let tx_bytes = TxRaw {
+            body_bytes: sign_doc.body_bytes,
+            auth_info_bytes: sign_doc.auth_info_bytes,
+            signatures: vec![vec![]],
+        }
+        .encode_to_vec();
+
+        #[allow(deprecated)]
+        let response = self
+            .wasm_client
+            .query(|client| {
+                let tx_bytes = tx_bytes.clone();
+                async move {
+                    ServiceClient::new(client)
+                        .simulate(SimulateRequest { tx: None, tx_bytes })
+                        .await
+                }
+            })
+            .await
+            .map_err(|status| Error::Secret(status.message().to_string()))?;
+
+        response
+            .into_inner()
+            .gas_info
+            .map(|gas_info| gas_info.gas_used)
+            .ok_or_else(|| Error::generic("simulation returned no gas info"))
+    }
+
+    /// Builds the transaction and has the wallet sign it, in amino mode if that's all it can do.
+    /// Returns the encoded `TxRaw`.
+    pub async fn sign(&self, msgs: &[EncodedMsg], options: &TxOptions) -> Result<Vec<u8>, Error> {
+        let account = self.account().await?;
+        match self.signer {
+            KeplrSigner::Direct(_) => self.sign_direct(&account, msgs, options).await,
+            KeplrSigner::OnlyAmino(_) => self.sign_amino(&account, msgs, options).await,
+        }
+    }
+
+    async fn sign_direct(
+        &self,
+        account: &BaseAccount,
+        msgs: &[EncodedMsg],
+        options: &TxOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let sign_doc = self.sign_doc(account, msgs, options)?;
+
+        debug!("signing tx with {} messages", msgs.len());
+        let response = self
+            .signer
+            .sign_direct(self.address(), SignDocVariant::SignDoc(sign_doc))
+            .await?;
+
+        // the wallet may have changed the fee or memo, so use the document it actually signed
+        let SignDocVariant::SignDoc(signed) = response.signed else {
+            return Err(Error::generic("unexpected sign doc format"));
+        };
+        let signature = signature_bytes(&response.signature)?;
+
+        Ok(TxRaw {
+            body_bytes: signed.body_bytes,
+            auth_info_bytes: signed.auth_info_bytes,
+            signatures: vec![signature],
+        }
+        .encode_to_vec())
+    }
+
+    /// Signs the amino JSON form of `msgs`, then builds the protobuf transaction around the
+    /// signature with `SIGN_MODE_LEGACY_AMINO_JSON`, which the chain verifies the same way.
+    async fn sign_amino(
+        &self,
+        account: &BaseAccount,
+        msgs: &[EncodedMsg],
+        options: &TxOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let amino_msgs = msgs
+            .iter()
+            .map(|msg| {
+                msg.amino.clone().ok_or_else(|| {
+                    Error::generic(format!(
+                        "{} can't be signed by a hardware wallet",
+                        msg.any.type_url
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (fee_denom, fee_amount) = self.fee(options)?;
+        let sign_doc: StdSignDoc = serde_json::from_value(json!({
+            "chain_id": self.chain.chain_id,
+            "account_number": account.account_number.to_string(),
+            "sequence": account.sequence.to_string(),
+            "fee": {
+                "amount": [{ "denom": fee_denom, "amount": fee_amount.to_string() }],
+                "gas": options.gas_limit.to_string(),
+            },
+            "msgs": amino_msgs,
+            "memo": options.memo,
+        }))
+        .map_err(Error::generic)?;
+
+        debug!("signing tx with {} messages in amino mode", msgs.len());
+        let response = self.signer.sign_amino(self.address(), sign_doc).await?;
+
+        // the wallet may have changed the fee or memo, so use the document it actually signed
+        let signed = serde_json::to_value(&response.signed).map_err(Error::generic)?;
+        let memo = signed["memo"].as_str().unwrap_or_default().to_string();
+        let gas_limit = signed["fee"]["gas"]
+            .as_str()
+            .and_then(|gas| gas.parse().ok())
+            .ok_or_else(|| Error::generic("signed doc has no gas limit"))?;
+        let amount = signed["fee"]["amount"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|coin| {
+                Ok(secretrs::Coin {
+                    denom: coin["denom"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .parse()
+                        .map_err(Error::generic)?,
+                    amount: coin["amount"]
+                        .as_str()
+                        .and_then(|amount| amount.parse().ok())
+                        .ok_or_else(|| Error::generic("signed doc has an invalid fee"))?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let fee = Fee {
+            amount,
+            gas_limit,
+            payer: None,
+            granter: None,
+        };
+
+        let body = Body::new(msgs.iter().map(|msg| msg.any.clone()), memo, 0u32);
+        let auth_info = SignerInfo {
+            public_key: Some(self.public_key()?.into()),
+            mode_info: ModeInfo::single(SignMode::LegacyAminoJson),
+            sequence: account.sequence,
+        }
+        .auth_info(fee);
+        let signature = signature_bytes(&response.signature)?;
+
+        Ok(TxRaw {
+            body_bytes: body.into_bytes().map_err(Error::generic)?,
+            auth_info_bytes: auth_info.into_bytes().map_err(Error::generic)?,
+            signatures: vec![signature],
+        }
+        .encode_to_vec())
+    }
+
+    /// Submits a signed transaction and returns its hash once it passed `CheckTx`.
+    pub async fn broadcast(&self, tx_bytes: Vec<u8>) -> Result<String, Error> {
+        let response = self
+            .wasm_client
+            .send(|client| {
+                let tx_bytes = tx_bytes.clone();
+                async move {
+                    ServiceClient::new(client)
+                        .broadcast_tx(BroadcastTxRequest {
+                            tx_bytes,
+                            mode: BroadcastMode::Sync as i32,
+                        })
+                        .await
+                }
+            })
+            .await
+            .map_err(|status| Error::Secret(status.message().to_string()))?
+            .into_inner()
+            .tx_response
+            .ok_or_else(|| Error::generic("empty broadcast response"))?;
+
+        if response.code != 0 {
+            return Err(Error::Secret(response.raw_log));
+        }
+        debug!("broadcast {}", response.txhash);
+
+        Ok(response.txhash)
+    }
+
+    /// Signs and broadcasts the transaction, then follows it with `tracker`, which shows its
+    /// progress as `description`. Fails if it was included but failed.
+    ///
+    /// The account's cached history is dropped afterwards, since it now has another tx.
+    pub async fn sign_and_track(
+        &self,
+        msgs: &[EncodedMsg],
+        options: &TxOptions,
+        tracker: TxTracker,
+        description: impl Into<String>,
+    ) -> Result<TxResponse, Error> {
+        let tx_bytes = self.sign(msgs, options).await?;
+        let tx_hash = self.broadcast(tx_bytes).await?;
+        let response = tracker.track(tx_hash, description).await;
+        // failed txs are in the history too
+        history::invalidate_latest(self.chain.chain_id, self.address());
+        response
+    }
+
+    /// Decrypts the data returned by each contract execution in `msgs`, in order. Other messages
+    /// get `None`.
+    pub async fn decrypt_responses(
+        &self,
+        response: &TxResponse,
+        msgs: &[EncodedMsg],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let data = hex_decode(&response.data)?;
+        let tx_msg_data = TxMsgData::decode(data.as_slice()).map_err(Error::generic)?;
+
+        // older chains fill `data`, newer ones `msg_responses`
+        #[allow(deprecated)]
+        let responses: Vec<Vec<u8>> = match tx_msg_data.msg_responses.is_empty() {
+            true => tx_msg_data.data.into_iter().map(|data| data.data).collect(),
+            false => tx_msg_data
+                .msg_responses
+                .into_iter()
+                .map(|any| any.value)
+                .collect(),
+        };
+
+        let mut decrypted = Vec::with_capacity(msgs.len());
+        for (msg, response) in msgs.iter().zip(responses) {
+            let Some(nonce) = msg.nonce else {
+                decrypted.push(None);
+                continue;
+            };
+            let response =
+                MsgExecuteContractResponse::decode(response.as_slice()).map_err(Error::generic)?;
+            if response.data.is_empty() {
+                decrypted.push(None);
+                continue;
+            }
+            let data = self.enigma.decrypt(&response.data, &nonce).await?;
+            // the contract's response is base64 encoded before encryption
+            decrypted.push(Some(BASE64_STANDARD.decode(&data).map_err(Error::generic)?));
+        }
+
+        Ok(decrypted)
+    }
+}
+
+/// The transaction's result, or `None` if it isn't in a block (yet).
+pub async fn get_tx(client: Client, tx_hash: &str) -> Result<Option<TxResponse>, Error> {
+    let request = GetTxRequest {
+        hash: tx_hash.to_string(),
+    };
+    match ServiceClient::new(client).get_tx(request).await {
+        Ok(response) => Ok(response.into_inner().tx_response),
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+        Err(status) => Err(Error::Secret(status.message().to_string())),
+    }
+}
+
+/// The raw bytes of a wallet's `{ pub_key, signature }` response, whose signature is base64.
+fn signature_bytes(signature: &impl Serialize) -> Result<Vec<u8>, Error> {
+    serde_json::to_value(signature)
+        .ok()
+        .and_then(|signature| signature["signature"].as_str().map(str::to_string))
+        .and_then(|signature| BASE64_STANDARD.decode(signature).ok())
+        .ok_or_else(|| Error::from(keplr::Error::InvalidSignature("not base64".into())))
+}
+
+/// Coins as amino JSON has them, with the amount as a string.
+fn amino_coins(coins: &[Coin]) -> Value {
+    coins
+        .iter()
+        .map(|coin| json!({ "denom": coin.denom, "amount": coin.amount.raw().to_string() }))
+        .collect()
+}
+
+fn to_secretrs_coins(coins: &[Coin]) -> Result<Vec<secretrs::Coin>, Error> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(secretrs::Coin {
+                denom: coin.denom.parse().map_err(Error::generic)?,
+                amount: coin.amount.raw(),
+            })
+        })
+        .collect()
+}
+
+fn hex_decode(hex: &str) -> Result<Vec<u8>, Error> {
+    if hex.len() % 2 != 0 {
+        return Err(Error::generic("odd length hex string"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(Error::generic))
+        .collect()
+}
@@ -0,0 +1,162 @@
+//! Following broadcast transactions until they're included in a block.
+//!
+//! [`TxTracker`] is provided as context. Every transaction it follows stays in
+//! [`TxTracker::txs`], so the UI can show what's pending and how finished ones went.
+
+use crate::{error::Error, state::WasmClient, tx::get_tx, utils::sleep};
+use leptos::prelude::*;
+use secretrs::proto::cosmos::base::abci::v1beta1::TxResponse;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How often [`TxTracker::track`] asks whether a transaction made it into a block.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Blocks take ~6 seconds, so a transaction that isn't included after this is probably lost.
+pub const POLL_TIMEOUT: Duration = Duration::from_secs(60);
+/// Successful transactions disappear from the toasts after this long.
+const DISMISS_AFTER: Duration = Duration::from_secs(10);
+
+/// The decoded outcome of an included transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxResult {
+    pub tx_hash: String,
+    pub height: i64,
+    /// 0 on success, otherwise an error code of `codespace`.
+    pub code: u32,
+    pub codespace: String,
+    pub log: String,
+    pub gas_used: i64,
+    pub gas_wanted: i64,
+}
+
+impl TxResult {
+    pub fn is_success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+impl From<&TxResponse> for TxResult {
+    fn from(response: &TxResponse) -> Self {
+        Self {
+            tx_hash: response.txhash.clone(),
+            height: response.height,
+            code: response.code,
+            codespace: response.codespace.clone(),
+            log: response.raw_log.clone(),
+            gas_used: response.gas_used,
+            gas_wanted: response.gas_wanted,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxStatus {
+    Pending,
+    Included(TxResult),
+    /// Not included within [`POLL_TIMEOUT`]. It may still land later.
+    TimedOut,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedTx {
+    pub tx_hash: String,
+    pub description: String,
+    pub status: TxStatus,
+    /// Hidden from the toasts.
+    pub dismissed: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct TxTracker {
+    wasm_client: WasmClient,
+    /// Oldest first.
+    pub txs: RwSignal<Vec<TrackedTx>>,
+    /// The txs still waiting for a block, oldest first.
+    pub pending: Memo<Vec<TrackedTx>>,
+    /// The txs that were included or timed out, oldest first.
+    pub finished: Memo<Vec<TrackedTx>>,
+}
+
+impl TxTracker {
+    pub fn new(wasm_client: WasmClient) -> Self {
+        let txs = RwSignal::new(Vec::<TrackedTx>::new());
+        let with_status = move |pending: bool| {
+            Memo::new(move |_| {
+                txs.with(|txs| {
+                    txs.iter()
+                        .filter(|tx| (tx.status == TxStatus::Pending) == pending)
+                        .cloned()
+                        .collect()
+                })
+            })
+        };
+
+        Self {
+            wasm_client,
+            txs,
+            pending: with_status(true),
+            finished: with_status(false),
+        }
+    }
+
+    pub fn dismiss(&self, tx_hash: &str) {
+        self.update(tx_hash, |tx| tx.dismissed = true);
+    }
+
+    fn update(&self, tx_hash: &str, f: impl FnOnce(&mut TrackedTx)) {
+        self.txs.update(|txs| {
+            if let Some(tx) = txs.iter_mut().find(|tx| tx.tx_hash == tx_hash) {
+                f(tx)
+            }
+        });
+    }
+
+    /// Polls until the transaction is included or [`POLL_TIMEOUT`] runs out, keeping
+    /// [`Self::txs`] up to date. Fails if the transaction failed or timed out.
+    pub async fn track(
+        &self,
+        tx_hash: String,
+        description: impl Into<String>,
+    ) -> Result<TxResponse, Error> {
+        self.txs.update(|txs| {
+            txs.push(TrackedTx {
+                tx_hash: tx_hash.clone(),
+                description: description.into(),
+                status: TxStatus::Pending,
+                dismissed: false,
+            })
+        });
+        debug!("tracking {tx_hash}");
+
+        let attempts = POLL_TIMEOUT.as_millis() / POLL_INTERVAL.as_millis();
+        for _ in 0..attempts {
+            sleep(POLL_INTERVAL).await;
+            // looked up every time, in case of a failover in the meantime
+            let response = match get_tx(self.wasm_client.get_untracked(), &tx_hash).await {
+                Ok(Some(response)) => response,
+                Ok(None) => continue,
+                Err(error) => {
+                    warn!("polling {tx_hash}: {error}");
+                    continue;
+                }
+            };
+
+            let result = TxResult::from(&response);
+            let success = result.is_success();
+            self.update(&tx_hash, |tx| tx.status = TxStatus::Included(result));
+
+            if !success {
+                return Err(Error::Secret(response.raw_log));
+            }
+            let tracker = *self;
+            set_timeout(move || tracker.dismiss(&tx_hash), DISMISS_AFTER);
+            return Ok(response);
+        }
+
+        self.update(&tx_hash, |tx| tx.status = TxStatus::TimedOut);
+        Err(Error::generic(format!(
+            "{tx_hash} was not included within {} seconds",
+            POLL_TIMEOUT.as_secs()
+        )))
+    }
+}
@@ -1,5 +1,19 @@
 use leptos::prelude::window;
+use std::time::Duration;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::Promise;
 
 pub fn alert(msg: impl AsRef<str>) {
     let _ = window().alert_with_message(msg.as_ref());
 }
+
+/// Resolves after `duration`, using `setTimeout`.
+pub async fn sleep(duration: Duration) {
+    let promise = Promise::new(&mut |resolve, _| {
+        let _ = window().set_timeout_with_callback_and_timeout_and_arguments_0(
+            &resolve,
+            duration.as_millis() as i32,
+        );
+    });
+    let _ = JsFuture::from(promise).await;
+}
@@ -0,0 +1,175 @@
+//! Creating or setting SNIP-20 viewing keys on chain, then registering them in the wallet.
+//!
+//! Keys for any number of tokens are set up in a single transaction.
+
+use crate::{
+    error::Error,
+    fee::FeeChoice,
+    keplr::{tokens::ContractInfo, WalletProvider},
+    state::KeplrSignals,
+    tx::{ExecuteMsg, SigningClient},
+    tx_tracker::TxTracker,
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// Gas for one `create_viewing_key` or `set_viewing_key` message, with some headroom.
+const GAS_PER_KEY: u64 = 40_000;
+const BASE_GAS: u64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewingKeyMethod {
+    /// The contract derives the key, seeded with entropy from the browser.
+    Create,
+    /// The browser generates the key and the contract stores it.
+    Set,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Snip20ExecuteMsg {
+    CreateViewingKey { entropy: String },
+    SetViewingKey { key: String },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Snip20ExecuteAnswer {
+    CreateViewingKey { key: String },
+}
+
+/// The gas limit for setting up `count` keys. The messages are cheap and predictable, so this
+/// isn't simulated.
+pub fn gas_limit(count: usize) -> u64 {
+    BASE_GAS + GAS_PER_KEY * count as u64
+}
+
+/// 32 bytes from the browser's CSPRNG, base64 encoded.
+pub fn random_key() -> Result<String, Error> {
+    let mut bytes = [0u8; 32];
+    window()
+        .crypto()
+        .and_then(|crypto| crypto.get_random_values_with_u8_array(&mut bytes))
+        .map_err(|_| Error::generic("the browser has no secure random number generator"))?;
+
+    Ok(BASE64_STANDARD.encode(bytes))
+}
+
+/// Sets up a viewing key for each of `tokens` in one transaction (followed by `tx_tracker`) that
+/// pays `fee`, registers the keys in the wallet and caches them. Returns
+/// `(contract_address, key)` pairs.
+///
+/// `code_hashes` must be in the same order as `tokens`.
+pub async fn setup_viewing_keys(
+    client: &SigningClient,
+    keplr: KeplrSignals,
+    tx_tracker: TxTracker,
+    tokens: &[ContractInfo],
+    code_hashes: &[String],
+    method: ViewingKeyMethod,
+    fee: FeeChoice,
+) -> Result<Vec<(String, String)>, Error> {
+    if tokens.len() != code_hashes.len() {
+        return Err(Error::generic(format!(
+            "{} tokens but {} code hashes",
+            tokens.len(),
+            code_hashes.len()
+        )));
+    }
+    if tokens.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut keys = Vec::with_capacity(tokens.len());
+    let mut msgs = Vec::with_capacity(tokens.len());
+    for (token, code_hash) in tokens.iter().zip(code_hashes) {
+        let (msg, key) = match method {
+            ViewingKeyMethod::Create => (
+                Snip20ExecuteMsg::CreateViewingKey {
+                    entropy: random_key()?,
+                },
+                None,
+            ),
+            ViewingKeyMethod::Set => {
+                let key = random_key()?;
+                (
+                    Snip20ExecuteMsg::SetViewingKey { key: key.clone() },
+                    Some(key),
+                )
+            }
+        };
+        keys.push(key);
+        msgs.push(
+            client
+                .execute_msg(&ExecuteMsg {
+                    contract_address: token.contract_address.clone(),
+                    code_hash: code_hash.clone(),
+                    msg,
+                    sent_funds: vec![],
+                })
+                .await?,
+        );
+    }
+
+    let options = fee
+        .fee(client.chain(), gas_limit(tokens.len()))
+        .ok_or_else(|| Error::generic("the chain has no fee currency"))?
+        .tx_options("");
+    debug!("setting up {} viewing keys", tokens.len());
+    let description = match tokens.len() {
+        1 => "Set up a viewing key".to_string(),
+        n => format!("Set up {n} viewing keys"),
+    };
+    let response = client
+        .sign_and_track(&msgs, &options, tx_tracker, description)
+        .await?;
+
+    // keys made by the contract only exist in its (encrypted) responses
+    if method == ViewingKeyMethod::Create {
+        let answers = client.decrypt_responses(&response, &msgs).await?;
+        if answers.len() != msgs.len() {
+            return Err(Error::generic(format!(
+                "expected {} create_viewing_key answers, got {}",
+                msgs.len(),
+                answers.len()
+            )));
+        }
+        for (key, answer) in keys.iter_mut().zip(answers) {
+            let answer =
+                answer.ok_or_else(|| Error::generic("missing create_viewing_key answer"))?;
+            let Snip20ExecuteAnswer::CreateViewingKey { key: created } =
+                serde_json::from_slice(&answer).map_err(Error::generic)?;
+            *key = Some(created);
+        }
+    }
+
+    let provider = keplr.wallet.get_untracked().provider();
+    let chain_id = client.chain().chain_id;
+    let mut results = Vec::with_capacity(tokens.len());
+    for (token, key) in tokens.iter().zip(keys) {
+        let key = key.ok_or_else(|| {
+            Error::generic(format!("no viewing key for {}", token.contract_address))
+        })?;
+        keplr.cache.set_viewing_key(
+            chain_id,
+            client.address(),
+            &token.contract_address,
+            key.clone(),
+        );
+        // the key is already set on chain, so a rejected popup only means Keplr won't know it
+        if let Err(error) = provider
+            .suggest_token(chain_id, &token.contract_address, Some(&key))
+            .await
+        {
+            warn!(
+                "{} was not added to the wallet: {error}",
+                token.metadata.symbol
+            );
+        }
+        results.push((token.contract_address.clone(), key));
+    }
+
+    Ok(results)
+}